@@ -52,13 +52,15 @@ impl Command for AsyncTaskCommand {
 
         let messages = self.ctx.messages.clone();
         let task_name_clone = task_name.clone();
+        // Prints above the prompt without mangling the line being typed
+        let printer = shell.printer();
 
         // Using the TaskPool's spawn_async method
         shell.spawn_async(&task_name, TaskOptions::default(), async move {
-            println!(
+            let _ = printer.print(&format!(
                 "Async task '{}' started (counting to {})",
                 task_name_clone, count
-            );
+            ));
 
             for i in 1..=count {
                 sleep(Duration::from_millis(500)).await;
@@ -67,11 +69,17 @@ impl Command for AsyncTaskCommand {
                 msgs.push(format!("Task '{}': {}/{}", task_name_clone, i, count));
 
                 if i % 5 == 0 {
-                    println!("Task '{}': reached {}/{}", task_name_clone, i, count);
+                    let _ = printer.print(&format!(
+                        "Task '{}': reached {}/{}",
+                        task_name_clone, i, count
+                    ));
                 }
             }
 
-            println!("Task '{}' finished counting to {}!", task_name_clone, count);
+            let _ = printer.print(&format!(
+                "Task '{}' finished counting to {}!",
+                task_name_clone, count
+            ));
             None
         });
 
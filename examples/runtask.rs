@@ -1,4 +1,4 @@
-use std::{error::Error, thread::sleep, time::Duration};
+use std::{error::Error, io::Write, thread::sleep, time::Duration};
 
 use hackshell::{Command, CommandResult, Hackshell, TaskOptions, error::HackshellError};
 
@@ -21,12 +21,15 @@ impl Command for RunTask {
         // .to_estring() comes from the hackshell::error::MapErrToString trait
         let n = cmd[1].parse::<u64>()?;
 
+        let shell = s.clone();
+
         s.spawn("runtask", TaskOptions::default(), move |run| {
             let mut c = 10;
-            println!("RunTask started. Use the `task` command to see it! This task will finish after 10 prints or can be terminated by issuing `task -t runtask`.\r");
+            let mut out = shell.stdout();
+            let _ = writeln!(out, "RunTask started. Use the `task` command to see it, and `task -f runtask` to see its output! This task will finish after 10 prints or can be terminated by issuing `task -t runtask`.");
 
             while c > 0 && run.load(std::sync::atomic::Ordering::Relaxed) {
-                println!("RunTask is running!\r");
+                let _ = writeln!(out, "RunTask is running!");
                 sleep(Duration::from_secs(n));
                 c -= 1;
             }
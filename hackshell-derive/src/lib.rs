@@ -0,0 +1,275 @@
+//! Derive macros for [hackshell](https://docs.rs/hackshell).
+//!
+//! Use them through the `derive` feature of `hackshell`, which re-exports them.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, DeriveInput, Expr, ExprArray, ExprLit, FnArg, ItemFn, Lit, LitStr,
+    meta::ParseNestedMeta, parse_macro_input, spanned::Spanned,
+};
+
+/// Attributes shared by `#[derive(Command)]` and `#[command]`.
+#[derive(Default)]
+struct CommandAttrs {
+    name: Option<LitStr>,
+    aliases: Vec<LitStr>,
+    category: Option<LitStr>,
+    help: Option<LitStr>,
+    is_async: bool,
+}
+
+impl CommandAttrs {
+    fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("category") {
+            self.category = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("help") {
+            self.help = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("aliases") {
+            let array: ExprArray = meta.value()?.parse()?;
+
+            for elem in array.elems {
+                match elem {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }) => self.aliases.push(s),
+                    other => return Err(syn::Error::new(other.span(), "expected a string")),
+                }
+            }
+        } else if meta.path.is_ident("asynchronous") {
+            self.is_async = true;
+        } else {
+            return Err(meta.error("unknown command attribute"));
+        }
+
+        Ok(())
+    }
+
+    /// Help from the doc comments, when not given explicitly.
+    fn help_or_docs(&self, attrs: &[Attribute], span: proc_macro2::Span) -> LitStr {
+        if let Some(help) = &self.help {
+            return help.clone();
+        }
+
+        let docs: Vec<String> = attrs
+            .iter()
+            .filter(|a| a.path().is_ident("doc"))
+            .filter_map(|a| match &a.meta.require_name_value().ok()?.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            })
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        LitStr::new(&docs.join(" "), span)
+    }
+
+    /// Common part of the `Command`/`AsyncCommand` impls.
+    fn metadata(&self, default_name: &str, help: LitStr) -> TokenStream2 {
+        let name = self
+            .name
+            .clone()
+            .map(|n| n.value())
+            .unwrap_or_else(|| default_name.to_string());
+        let aliases = &self.aliases;
+
+        let category = self.category.as_ref().map(|c| {
+            quote! {
+                fn category(&self) -> &'static str {
+                    #c
+                }
+            }
+        });
+
+        quote! {
+            fn commands(&self) -> &'static [&'static str] {
+                &[#name #(, #aliases)*]
+            }
+
+            fn help(&self) -> &'static str {
+                #help
+            }
+
+            #category
+        }
+    }
+}
+
+/// Implement `hackshell::Command` for a struct.
+///
+/// The command name defaults to the lowercase struct name and the help to its
+/// doc comments. The generated `run` forwards to an inherent method
+/// `fn execute(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult`.
+///
+/// With `#[command(asynchronous)]` the `hackshell::AsyncCommand` trait is
+/// implemented instead, forwarding to an `async fn execute`.
+///
+/// ```ignore
+/// /// Prints the target
+/// #[derive(Command)]
+/// #[command(name = "target", aliases = ["tgt"], category = "Recon")]
+/// struct Target;
+///
+/// impl Target {
+///     fn execute(&self, s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+///         Ok(s.get_var("target"))
+///     }
+/// }
+/// ```
+#[proc_macro_derive(Command, attributes(command))]
+pub fn derive_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive_command_inner(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn derive_command_inner(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut attrs = CommandAttrs::default();
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("command")) {
+        attr.parse_nested_meta(|meta| attrs.parse_meta(meta))?;
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let help = attrs.help_or_docs(&input.attrs, ident.span());
+    let metadata = attrs.metadata(&ident.to_string().to_lowercase(), help);
+
+    let imp = if attrs.is_async {
+        quote! {
+            #[::hackshell::async_trait]
+            impl #impl_generics ::hackshell::AsyncCommand for #ident #ty_generics #where_clause {
+                #metadata
+
+                async fn run(
+                    &self,
+                    s: &::hackshell::Hackshell,
+                    cmd: &[&str],
+                ) -> ::hackshell::CommandResult {
+                    self.execute(s, cmd).await
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics ::hackshell::Command for #ident #ty_generics #where_clause {
+                #metadata
+
+                fn run(
+                    &self,
+                    s: &::hackshell::Hackshell,
+                    cmd: &[&str],
+                ) -> ::hackshell::CommandResult {
+                    self.execute(s, cmd)
+                }
+            }
+        }
+    };
+
+    Ok(imp)
+}
+
+/// Turn a function into a command.
+///
+/// The function must take the shell and the raw words, like `Command::run`, and
+/// is replaced by a unit struct of the same name that can be registered with
+/// `add_command` (or `add_async_command` for an `async fn`). The command name
+/// defaults to the function name and the help to its doc comments.
+///
+/// ```ignore
+/// /// Greets someone
+/// #[hackshell::command(name = "greet", aliases = ["hi"], category = "Fun")]
+/// fn greet(_s: &Hackshell, cmd: &[&str]) -> CommandResult {
+///     Ok(Some(format!("Hello {}", cmd.get(1).unwrap_or(&"world"))))
+/// }
+///
+/// shell.add_command(greet);
+/// ```
+#[proc_macro_attribute]
+pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut attrs = CommandAttrs::default();
+    let parser = syn::meta::parser(|meta| attrs.parse_meta(meta));
+    parse_macro_input!(attr with parser);
+
+    let func = parse_macro_input!(item as ItemFn);
+
+    command_inner(attrs, func)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn command_inner(mut attrs: CommandAttrs, func: ItemFn) -> syn::Result<TokenStream2> {
+    let inputs = &func.sig.inputs;
+
+    if inputs.len() != 2 || inputs.iter().any(|i| matches!(i, FnArg::Receiver(_))) {
+        return Err(syn::Error::new(
+            inputs.span(),
+            "expected `fn(s: &Hackshell, cmd: &[&str]) -> CommandResult`",
+        ));
+    }
+
+    attrs.is_async |= func.sig.asyncness.is_some();
+
+    let ident = &func.sig.ident;
+    let vis = &func.vis;
+    let help = attrs.help_or_docs(&func.attrs, ident.span());
+    let metadata = attrs.metadata(&ident.to_string(), help);
+
+    // The function is kept as an associated function of the generated struct
+    let mut inner = func.clone();
+    inner.sig.ident = format_ident!("__{}", ident);
+    inner.vis = syn::Visibility::Inherited;
+    inner.attrs.retain(|a| !a.path().is_ident("doc"));
+    let inner_ident = &inner.sig.ident;
+    let docs = func.attrs.iter().filter(|a| a.path().is_ident("doc"));
+
+    let imp = if attrs.is_async {
+        quote! {
+            #[::hackshell::async_trait]
+            impl ::hackshell::AsyncCommand for #ident {
+                #metadata
+
+                async fn run(
+                    &self,
+                    s: &::hackshell::Hackshell,
+                    cmd: &[&str],
+                ) -> ::hackshell::CommandResult {
+                    Self::#inner_ident(s, cmd).await
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl ::hackshell::Command for #ident {
+                #metadata
+
+                fn run(
+                    &self,
+                    s: &::hackshell::Hackshell,
+                    cmd: &[&str],
+                ) -> ::hackshell::CommandResult {
+                    Self::#inner_ident(s, cmd)
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #(#docs)*
+        #[allow(non_camel_case_types)]
+        #vis struct #ident;
+
+        impl #ident {
+            #inner
+        }
+
+        #imp
+    })
+}
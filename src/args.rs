@@ -0,0 +1,478 @@
+//! Typed argument specifications for commands.
+//!
+//! A command can describe its arguments with an [`ArgSpec`] returned by
+//! [`crate::Command::spec`]. The shell then validates every invocation before
+//! running the command and hands it the parsed [`Args`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use crate::error::{HackshellError, HackshellResult};
+
+/// Type an argument value must be parsable as.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArgType {
+    #[default]
+    String,
+    /// Signed integer (`i64`)
+    Int,
+    /// Unsigned integer (`u64`)
+    Uint,
+    /// Floating point number (`f64`)
+    Float,
+    /// `true` or `false`
+    Bool,
+}
+
+impl ArgType {
+    fn check(&self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Int => value.parse::<i64>().is_ok(),
+            Self::Uint => value.parse::<u64>().is_ok(),
+            Self::Float => value.parse::<f64>().is_ok(),
+            Self::Bool => value.parse::<bool>().is_ok(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Int => "integer",
+            Self::Uint => "unsigned integer",
+            Self::Float => "number",
+            Self::Bool => "boolean",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArgKind {
+    Positional,
+    Flag,
+    Option,
+}
+
+/// A single argument of an [`ArgSpec`].
+///
+/// Positionals are required by default, flags and options are optional.
+#[derive(Clone, Debug)]
+pub struct Arg {
+    name: &'static str,
+    kind: ArgKind,
+    short: Option<char>,
+    ty: ArgType,
+    required: bool,
+    variadic: bool,
+    default: Option<&'static str>,
+    value_name: Option<&'static str>,
+    help: &'static str,
+}
+
+impl Arg {
+    fn new(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            short: None,
+            ty: ArgType::String,
+            required: kind == ArgKind::Positional,
+            variadic: false,
+            default: None,
+            value_name: None,
+            help: "",
+        }
+    }
+
+    /// A positional argument, matched by its position.
+    pub fn positional(name: &'static str) -> Self {
+        Self::new(name, ArgKind::Positional)
+    }
+
+    /// A boolean switch, given as `--name` or `-s`.
+    pub fn flag(name: &'static str) -> Self {
+        Self::new(name, ArgKind::Flag)
+    }
+
+    /// A named argument taking a value, given as `--name value`, `--name=value` or `-s value`.
+    pub fn option(name: &'static str) -> Self {
+        Self::new(name, ArgKind::Option)
+    }
+
+    /// Short alias (`-s`) of a flag or option.
+    pub fn short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    /// Type the value must be parsable as, [`ArgType::String`] by default.
+    pub fn value_type(mut self, ty: ArgType) -> Self {
+        self.ty = ty;
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    /// Value used when the argument is not given. Implies [`Arg::optional`].
+    pub fn default(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self.required = false;
+        self
+    }
+
+    /// Let a positional argument collect all the remaining positional values.
+    /// Only meaningful for the last positional.
+    pub fn variadic(mut self) -> Self {
+        self.variadic = true;
+        self
+    }
+
+    /// Name of the value shown in usage messages, defaults to the argument name.
+    pub fn value_name(mut self, value_name: &'static str) -> Self {
+        self.value_name = Some(value_name);
+        self
+    }
+
+    pub fn help(mut self, help: &'static str) -> Self {
+        self.help = help;
+        self
+    }
+
+    fn value_name_or_name(&self) -> &'static str {
+        self.value_name.unwrap_or(self.name)
+    }
+
+    fn display_positional(&self) -> String {
+        let dots = if self.variadic { "..." } else { "" };
+
+        if self.required {
+            format!("<{}>{}", self.value_name_or_name(), dots)
+        } else {
+            format!("[{}]{}", self.value_name_or_name(), dots)
+        }
+    }
+
+    fn display_switch(&self) -> String {
+        let mut s = match self.short {
+            Some(c) => format!("-{}, --{}", c, self.name),
+            None => format!("--{}", self.name),
+        };
+
+        if self.kind == ArgKind::Option {
+            s.push_str(&format!(" <{}>", self.value_name_or_name()));
+        }
+
+        s
+    }
+}
+
+/// Argument specification of a command.
+///
+/// ```
+/// use hackshell::args::{Arg, ArgSpec, ArgType};
+///
+/// let spec = ArgSpec::new()
+///     .arg(Arg::positional("host"))
+///     .arg(Arg::option("port").short('p').value_type(ArgType::Uint).default("80"))
+///     .arg(Arg::flag("verbose").short('v'));
+///
+/// let args = spec.parse(&["connect", "-v", "example.com"]).unwrap();
+///
+/// assert_eq!(args.get_str("host"), Some("example.com"));
+/// assert_eq!(args.get::<u16>("port"), Some(80));
+/// assert!(args.flag("verbose"));
+///
+/// assert!(spec.parse(&["connect"]).is_err());
+/// assert!(spec.parse(&["connect", "example.com", "--port", "http"]).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ArgSpec {
+    args: Vec<Arg>,
+}
+
+impl ArgSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn arg(mut self, arg: Arg) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    fn positionals(&self) -> impl Iterator<Item = &Arg> {
+        self.args.iter().filter(|a| a.kind == ArgKind::Positional)
+    }
+
+    fn switches(&self) -> impl Iterator<Item = &Arg> {
+        self.args.iter().filter(|a| a.kind != ArgKind::Positional)
+    }
+
+    fn find_long(&self, long: &str) -> Option<&Arg> {
+        self.switches().find(|a| a.name == long)
+    }
+
+    fn find_short(&self, short: char) -> Option<&Arg> {
+        self.switches().find(|a| a.short == Some(short))
+    }
+
+    /// One line usage, e.g. `Usage: task [OPTIONS] <name>`.
+    pub fn usage(&self, command: &str) -> String {
+        let mut usage = format!("Usage: {}", command);
+
+        if self.switches().next().is_some() {
+            usage.push_str(" [OPTIONS]");
+        }
+
+        for arg in self.positionals() {
+            usage.push(' ');
+            usage.push_str(&arg.display_positional());
+        }
+
+        usage
+    }
+
+    /// Usage followed by the description of every argument.
+    pub fn help_text(&self, command: &str) -> String {
+        let mut text = self.usage(command);
+        text.push('\n');
+
+        let sections = [
+            ("Arguments", self.positionals().collect::<Vec<_>>()),
+            ("Options", self.switches().collect::<Vec<_>>()),
+        ];
+
+        for (title, args) in sections {
+            if args.is_empty() {
+                continue;
+            }
+
+            text.push_str(&format!("\n{}:\n", title));
+
+            for arg in args {
+                let name = match arg.kind {
+                    ArgKind::Positional => arg.display_positional(),
+                    _ => arg.display_switch(),
+                };
+
+                let mut help = arg.help.to_string();
+
+                if let Some(default) = arg.default {
+                    help.push_str(&format!(" [default: {}]", default));
+                }
+
+                text.push_str(&format!("  {:<22}  {}\n", name, help.trim()));
+            }
+        }
+
+        text
+    }
+
+    fn error(&self, command: &str, message: String) -> HackshellError {
+        HackshellError::Usage {
+            message,
+            usage: self.usage(command),
+        }
+    }
+
+    fn check(&self, command: &str, arg: &Arg, value: &str) -> HackshellResult<()> {
+        if arg.ty.check(value) {
+            Ok(())
+        } else {
+            Err(self.error(
+                command,
+                format!(
+                    "Invalid value '{}' for {}: expected {}",
+                    value,
+                    arg.value_name_or_name(),
+                    arg.ty.name()
+                ),
+            ))
+        }
+    }
+
+    /// Validate `cmd` (command name included) against the specification.
+    pub fn parse(&self, cmd: &[&str]) -> HackshellResult<Args> {
+        let command = cmd.first().copied().unwrap_or_default();
+        let mut args = Args::from_raw(cmd);
+        let mut positionals = vec![];
+        let mut only_positionals = false;
+        let mut words = cmd.iter().skip(1);
+
+        while let Some(&word) = words.next() {
+            if only_positionals || word == "-" || !word.starts_with('-') {
+                positionals.push(word);
+                continue;
+            }
+
+            if word == "--" {
+                only_positionals = true;
+                continue;
+            }
+
+            let (arg, inline) = if let Some(long) = word.strip_prefix("--") {
+                let (long, inline) = match long.split_once('=') {
+                    Some((long, value)) => (long, Some(value)),
+                    None => (long, None),
+                };
+
+                (self.find_long(long), inline)
+            } else {
+                let mut chars = word[1..].chars();
+
+                match (chars.next(), chars.as_str()) {
+                    (Some(c), "") => (self.find_short(c), None),
+                    (Some(c), rest) => (self.find_short(c), Some(rest)),
+                    (None, _) => (None, None),
+                }
+            };
+
+            // Words matching no switch, like negative numbers, are positionals
+            let Some(arg) = arg else {
+                positionals.push(word);
+                continue;
+            };
+
+            match arg.kind {
+                ArgKind::Flag => {
+                    if inline.is_some() {
+                        return Err(
+                            self.error(command, format!("Flag --{} takes no value", arg.name))
+                        );
+                    }
+
+                    args.flags.insert(arg.name.to_string());
+                }
+                _ => {
+                    let value = match inline {
+                        Some(value) => value,
+                        None => words.next().copied().ok_or_else(|| {
+                            self.error(command, format!("Missing value for --{}", arg.name))
+                        })?,
+                    };
+
+                    self.check(command, arg, value)?;
+
+                    args.values
+                        .insert(arg.name.to_string(), vec![value.to_string()]);
+                }
+            }
+        }
+
+        let mut positionals = positionals.into_iter();
+
+        for arg in self.positionals() {
+            let values: Vec<String> = if arg.variadic {
+                positionals.by_ref().map(|v| v.to_string()).collect()
+            } else {
+                positionals
+                    .next()
+                    .map(|v| v.to_string())
+                    .into_iter()
+                    .collect()
+            };
+
+            for value in values.iter() {
+                self.check(command, arg, value)?;
+            }
+
+            if !values.is_empty() {
+                args.values.insert(arg.name.to_string(), values);
+            }
+        }
+
+        if let Some(extra) = positionals.next() {
+            return Err(self.error(command, format!("Unexpected argument: {}", extra)));
+        }
+
+        for arg in self.args.iter() {
+            if args.values.contains_key(arg.name) || arg.kind == ArgKind::Flag {
+                continue;
+            }
+
+            if let Some(default) = arg.default {
+                args.values
+                    .insert(arg.name.to_string(), vec![default.to_string()]);
+            } else if arg.required {
+                let name = match arg.kind {
+                    ArgKind::Positional => arg.display_positional(),
+                    _ => format!("--{}", arg.name),
+                };
+
+                return Err(self.error(command, format!("Missing required argument {}", name)));
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Arguments of a command invocation, validated against an [`ArgSpec`].
+#[derive(Clone, Debug, Default)]
+pub struct Args {
+    raw: Vec<String>,
+    values: HashMap<String, Vec<String>>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    /// Arguments carrying only the raw words, as used for commands without a spec.
+    pub fn from_raw(cmd: &[&str]) -> Self {
+        Self {
+            raw: cmd.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// The command line as typed, command name included.
+    pub fn raw(&self) -> Vec<&str> {
+        self.raw.iter().map(|s| s.as_str()).collect()
+    }
+
+    /// Name the command has been invoked with.
+    pub fn command(&self) -> &str {
+        self.raw.first().map(|s| s.as_str()).unwrap_or_default()
+    }
+
+    /// Value of a positional or an option, if given or defaulted.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .and_then(|v| v.first())
+            .map(|s| s.as_str())
+    }
+
+    /// Value of a positional or an option parsed as `T`.
+    /// Returns `None` when missing or not parsable as `T`.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get_str(name).and_then(|v| v.parse().ok())
+    }
+
+    /// All the values collected by a variadic positional.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.values
+            .get(name)
+            .map(|v| v.iter().map(|s| s.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Check whether a flag has been given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// Check whether a positional or an option has a value.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::Write;
+
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
+
+pub struct Alias {}
+
+impl Command for Alias {
+    fn commands(&self) -> &'static [&'static str] {
+        &["alias"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Defines or lists aliases. Syntax: alias [name[='cmd args']]..."
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(
+            ArgSpec::new().arg(
+                Arg::positional("definition")
+                    .value_name("name[=value]")
+                    .optional()
+                    .variadic(),
+            ),
+        )
+    }
+
+    fn complete(&self, s: &Hackshell, _args: &[&str], _cursor: usize) -> Vec<String> {
+        s.aliases().into_keys().collect()
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        let definitions = args.get_all("definition");
+        let mut out = s.stdout();
+
+        if definitions.is_empty() {
+            let mut aliases: Vec<_> = s.aliases().into_iter().collect();
+            aliases.sort();
+
+            for (name, value) in aliases {
+                writeln!(out, "alias {}={}", name, quote(&value))?;
+            }
+
+            return Ok(None);
+        }
+
+        for definition in definitions {
+            match definition.split_once('=') {
+                Some((name, value)) => s.set_alias(name, value)?,
+                None => {
+                    let value = s
+                        .get_alias(definition)
+                        .ok_or_else(|| format!("{}: alias not found", definition))?;
+
+                    writeln!(out, "alias {}={}", definition, quote(&value))?;
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct Unalias {}
+
+impl Command for Unalias {
+    fn commands(&self) -> &'static [&'static str] {
+        &["unalias"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Removes aliases. Syntax: unalias [-a] <name>..."
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(
+            ArgSpec::new()
+                .arg(Arg::flag("all").short('a').help("Remove all aliases"))
+                .arg(Arg::positional("name").optional().variadic()),
+        )
+    }
+
+    fn complete(&self, s: &Hackshell, _args: &[&str], _cursor: usize) -> Vec<String> {
+        s.aliases().into_keys().collect()
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        if args.flag("all") {
+            for name in s.aliases().into_keys() {
+                s.unset_alias(&name);
+            }
+
+            return Ok(None);
+        }
+
+        let names = args.get_all("name");
+
+        if names.is_empty() {
+            return Err("Expected an alias name".into());
+        }
+
+        for name in names {
+            s.unset_alias(name)
+                .ok_or_else(|| format!("{}: alias not found", name))?;
+        }
+
+        Ok(None)
+    }
+}
+
+/// Quote a value so that it can be given back to `alias`.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -0,0 +1,63 @@
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
+
+pub struct Enter {}
+
+impl Command for Enter {
+    fn commands(&self) -> &'static [&'static str] {
+        &["enter"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Enters a command context. Syntax: enter <context>"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("context")))
+    }
+
+    fn complete(&self, s: &Hackshell, _args: &[&str], cursor: usize) -> Vec<String> {
+        if cursor != 1 {
+            return vec![];
+        }
+
+        s.contexts()
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        s.enter(args.get_str("context").unwrap_or_default())?;
+        Ok(None)
+    }
+}
+
+pub struct Back {}
+
+impl Command for Back {
+    fn commands(&self) -> &'static [&'static str] {
+        &["back"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Leaves the current command context"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn run(&self, s: &Hackshell, _: &[&str]) -> CommandResult {
+        s.back()?;
+        Ok(None)
+    }
+}
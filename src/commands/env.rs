@@ -1,3 +1,5 @@
+use std::io::Write;
+
 use crate::{Command, CommandResult, Hackshell};
 
 pub struct Env {}
@@ -16,8 +18,10 @@ impl Command for Env {
     }
 
     fn run(&self, s: &Hackshell, _: &[&str]) -> CommandResult {
+        let mut out = s.stdout();
+
         for v in s.env() {
-            println!("{}={}", v.0, v.1);
+            writeln!(out, "{}={}", v.0, v.1)?;
         }
 
         Ok(None)
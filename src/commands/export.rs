@@ -0,0 +1,53 @@
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
+
+pub struct Export {}
+
+impl Command for Export {
+    fn commands(&self) -> &'static [&'static str] {
+        &["export"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Sets a variable in the parent shell. Syntax: export <name> [value]"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(
+            ArgSpec::new()
+                .arg(Arg::positional("name"))
+                .arg(Arg::positional("value").optional()),
+        )
+    }
+
+    fn complete(&self, s: &Hackshell, _args: &[&str], cursor: usize) -> Vec<String> {
+        if cursor != 1 {
+            return vec![];
+        }
+
+        s.env().into_keys().collect()
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        let name = args.get_str("name").unwrap_or_default();
+
+        if let Some(value) = args.get_str("value") {
+            s.set_var(name, value);
+        }
+
+        s.export(name)?;
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, ArgType, Args},
+    commands::run_with_spec,
+    error::HackshellError,
+};
+
+/// Number of loops to act on, at most the number of enclosing ones.
+fn levels(s: &Hackshell, args: &Args) -> Result<usize, HackshellError> {
+    let n = args.get::<usize>("n").unwrap_or(1);
+
+    if s.loops() == 0 {
+        return Err(format!("{}: only meaningful in a loop", args.command()).into());
+    }
+
+    if n == 0 {
+        return Err(format!("{}: loop count out of range", args.command()).into());
+    }
+
+    Ok(n.min(s.loops()))
+}
+
+pub struct Break {}
+
+impl Command for Break {
+    fn commands(&self) -> &'static [&'static str] {
+        &["break"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Exits from n enclosing loops. Syntax: break [n]"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("n").value_type(ArgType::Uint).default("1")))
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        Err(HackshellError::Break(levels(s, args)?).into())
+    }
+}
+
+pub struct Continue {}
+
+impl Command for Continue {
+    fn commands(&self) -> &'static [&'static str] {
+        &["continue"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Resumes the next iteration of the nth enclosing loop. Syntax: continue [n]"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("n").value_type(ArgType::Uint).default("1")))
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        Err(HackshellError::Continue(levels(s, args)?).into())
+    }
+}
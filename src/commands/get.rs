@@ -1,4 +1,10 @@
-use crate::{Command, CommandResult, Hackshell};
+use std::io::Write;
+
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
 
 pub struct Get {}
 
@@ -15,12 +21,30 @@ impl Command for Get {
         "Shell"
     }
 
-    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
-        if cmd.len() != 2 {
-            return Err("Syntax: get <name>".into());
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("name")))
+    }
+
+    fn complete(&self, s: &Hackshell, _args: &[&str], cursor: usize) -> Vec<String> {
+        if cursor != 1 {
+            return vec![];
         }
 
-        println!("{}", s.get_var(&cmd[1]).ok_or("Variable not found")?);
+        s.env().into_keys().collect()
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        let name = args.get_str("name").unwrap_or_default();
+
+        writeln!(
+            s.stdout(),
+            "{}",
+            s.get_var(name).ok_or("Variable not found")?
+        )?;
 
         Ok(None)
     }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, io::Write};
 
 use crate::{Command, CommandEntry, CommandResult, Hackshell};
 
@@ -31,17 +31,31 @@ impl Command for Help {
             }
         }
 
+        let mut out = s.stdout();
+
         for (category, cmds) in by_category {
-            eprintln!("\n[{}]", category);
-            eprintln!("{:<24} {:<24}", "Command", "Description");
-            eprintln!("{:<24} {:<24}", "-------", "-----------");
+            writeln!(out, "\n[{}]", category)?;
+            writeln!(out, "{:<24} {:<24}", "Command", "Description")?;
+            writeln!(out, "{:<24} {:<24}", "-------", "-----------")?;
 
             for c in cmds {
-                eprintln!("{:<24} {:<24}", c.commands().join(", "), c.help());
+                writeln!(out, "{:<24} {:<24}", c.commands().join(", "), c.help())?;
+            }
+        }
+
+        let functions = s.functions();
+
+        if !functions.is_empty() {
+            writeln!(out, "\n[Functions]")?;
+            writeln!(out, "{:<24} {:<24}", "Function", "Description")?;
+            writeln!(out, "{:<24} {:<24}", "--------", "-----------")?;
+
+            for f in functions {
+                writeln!(out, "{:<24} {:<24}", f, "User-defined function")?;
             }
         }
 
-        eprintln!();
+        writeln!(out)?;
 
         Ok(None)
     }
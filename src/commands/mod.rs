@@ -1,8 +1,22 @@
+pub mod alias;
+pub mod context;
 pub mod env;
 pub mod exit;
+pub mod export;
+pub mod flow;
 pub mod get;
 pub mod help;
 pub mod set;
 pub mod sleep;
+pub mod source;
 pub mod task;
+pub mod test;
 pub mod unset;
+
+use crate::{Command, CommandResult, Hackshell};
+
+/// [`Command::run`] of the builtins declaring a spec: parse the raw words with it
+/// and call [`Command::run_args`].
+pub(crate) fn run_with_spec(c: &impl Command, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+    c.run_args(s, &c.spec().unwrap_or_default().parse(cmd)?)
+}
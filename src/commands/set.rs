@@ -1,4 +1,8 @@
-use crate::{Command, CommandResult, Hackshell};
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
 
 pub struct Set {}
 
@@ -15,12 +19,23 @@ impl Command for Set {
         "Shell"
     }
 
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(
+            ArgSpec::new()
+                .arg(Arg::positional("name"))
+                .arg(Arg::positional("value")),
+        )
+    }
+
     fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
-        if cmd.len() != 3 {
-            return Err("Syntax: set <name> <value>".into());
-        }
+        run_with_spec(self, s, cmd)
+    }
 
-        s.set_var(&cmd[1], &cmd[2]);
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        s.set_var(
+            args.get_str("name").unwrap_or_default(),
+            args.get_str("value").unwrap_or_default(),
+        );
 
         Ok(None)
     }
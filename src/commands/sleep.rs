@@ -1,6 +1,10 @@
 use std::{thread::sleep, time::Duration};
 
-use crate::{Command, CommandResult, Hackshell};
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, ArgType, Args},
+    commands::run_with_spec,
+};
 
 pub struct Sleep {}
 
@@ -17,13 +21,17 @@ impl Command for Sleep {
         "Shell"
     }
 
-    fn run(&self, _: &Hackshell, cmd: &[&str]) -> CommandResult {
-        if cmd.len() == 2 {
-            let duration = cmd[1].parse::<u64>().map_err(|e| e.to_string())?;
-            sleep(Duration::from_secs(duration));
-            Ok(None)
-        } else {
-            Err("Invalid number of arguments".into())
-        }
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("seconds").value_type(ArgType::Uint)))
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, _: &Hackshell, args: &Args) -> CommandResult {
+        let duration = args.get::<u64>("seconds").unwrap_or_default();
+        sleep(Duration::from_secs(duration));
+        Ok(None)
     }
 }
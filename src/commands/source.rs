@@ -0,0 +1,33 @@
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
+
+pub struct Source {}
+
+impl Command for Source {
+    fn commands(&self) -> &'static [&'static str] {
+        &["source"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Runs the commands of a file. Syntax: source <file>"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("file")))
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        Ok(s.source(args.get_str("file").unwrap_or_default())?)
+    }
+}
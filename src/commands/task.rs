@@ -1,14 +1,11 @@
-use crate::{Command, CommandResult, Hackshell, error::HackshellError};
+use std::io::Write;
 
-const TASK_HELP: &str = "\
-Usage: task [OPTIONS]
-
-Options:
-  -t, --terminate <name>  Terminate the task
-  -w, --wait <name>       Wait for the task (blocks until it ends)
-  --hidden                Show hidden tasks in the listing
-  -h, --help              Print this help message
-";
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+    error::HackshellError,
+};
 
 pub struct Task {}
 
@@ -25,59 +22,193 @@ impl Command for Task {
         "Shell"
     }
 
-    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
-        let mut include_hidden = false;
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(
+            ArgSpec::new()
+                .arg(
+                    Arg::option("terminate")
+                        .short('t')
+                        .value_name("name")
+                        .help("Terminate the task"),
+                )
+                .arg(
+                    Arg::option("wait")
+                        .short('w')
+                        .value_name("name")
+                        .help("Wait for the task (blocks until it ends)"),
+                )
+                .arg(
+                    Arg::option("logs")
+                        .short('l')
+                        .value_name("name")
+                        .help("Print the captured output of the task"),
+                )
+                .arg(
+                    Arg::option("follow")
+                        .short('f')
+                        .value_name("name")
+                        .help("Print the output of the task as it comes, until it ends"),
+                )
+                .arg(Arg::flag("history").help("List the ended tasks"))
+                .arg(Arg::flag("hidden").help("Show hidden tasks in the listing"))
+                .arg(Arg::flag("help").short('h').help("Print this help message")),
+        )
+    }
 
-        match cmd.get(1).map(|s| s.as_ref()) {
-            Some("-h" | "--help") => {
-                eprint!("{}", TASK_HELP);
-                return Ok(None);
+    fn complete(&self, s: &Hackshell, args: &[&str], cursor: usize) -> Vec<String> {
+        match cursor {
+            1 => [
+                "-t",
+                "--terminate",
+                "-w",
+                "--wait",
+                "-l",
+                "--logs",
+                "-f",
+                "--follow",
+                "--history",
+                "--hidden",
+                "-h",
+                "--help",
+            ]
+            .iter()
+            .map(|f| f.to_string())
+            .collect(),
+            2 if matches!(
+                args[1],
+                "-t" | "--terminate" | "-w" | "--wait" | "-l" | "--logs" | "-f" | "--follow"
+            ) =>
+            {
+                s.get_tasks_filtered(true)
+                    .into_iter()
+                    .map(|t| t.name)
+                    .collect()
             }
-            Some("-t" | "--terminate") => {
-                let name = cmd.get(2).ok_or("Missing task name for --terminate")?;
-                if s.is_protected(name).unwrap_or(false) {
-                    return Err(HackshellError::TaskIsProtected.into());
-                }
-                s.terminate(name)?;
-                return Ok(None);
-            }
-            Some("-w" | "--wait") => {
-                let name = cmd.get(2).ok_or("Missing task name for --wait")?;
-                s.join(name)?;
-                return Ok(None);
+            _ => vec![],
+        }
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        if args.flag("help") {
+            if let Some(spec) = self.spec() {
+                write!(s.stdout(), "{}", spec.help_text(args.command()))?;
             }
-            Some("--hidden") => {
-                include_hidden = true;
+            return Ok(None);
+        }
+
+        if let Some(name) = args.get_str("terminate") {
+            if s.is_protected(name).unwrap_or(false) {
+                return Err(HackshellError::TaskIsProtected.into());
             }
-            Some(flag) if flag.starts_with('-') => {
-                return Err(format!("Unknown flag: {}", flag).into());
+            s.terminate(name)?;
+            return Ok(None);
+        }
+
+        if let Some(name) = args.get_str("wait") {
+            s.join(name)?;
+            return Ok(None);
+        }
+
+        if let Some(name) = args.get_str("logs") {
+            let log = s.task_log(name).ok_or(HackshellError::TaskNotFound)?;
+            let mut out = s.stdout();
+
+            for line in log.lines() {
+                writeln!(out, "{}", line)?;
             }
-            _ => {}
+            return Ok(None);
+        }
+
+        if let Some(name) = args.get_str("follow") {
+            s.attach(name)?;
+            return Ok(None);
+        }
+
+        let include_hidden = args.flag("hidden");
+
+        if args.flag("history") {
+            return history(s, include_hidden);
         }
 
         let tasks = s.get_tasks_filtered(include_hidden);
+        let mut out = s.stdout();
 
         if tasks.is_empty() {
-            eprintln!("No running tasks");
+            writeln!(s.stderr(), "No running tasks")?;
             return Ok(None);
         }
 
         // Print a cool table header
-        eprintln!("\n{:<24} {:<24} {:<10}", "Task", "Started at", "Protected");
-        eprintln!("{:<24} {:<24} {:<10}\n", "----", "----------", "---------");
+        writeln!(
+            out,
+            "\n{:<24} {:<24} {:<10}",
+            "Task", "Started at", "Protected"
+        )?;
+        writeln!(
+            out,
+            "{:<24} {:<24} {:<10}\n",
+            "----", "----------", "---------"
+        )?;
 
         // For each task print its name, start time, and protected status
         for task in tasks {
-            eprintln!(
+            writeln!(
+                out,
                 "{:<24} {:<24} {:<10}",
                 task.name,
                 task.started.format("%Y-%m-%d %H:%M:%S"),
                 if task.protected { "yes" } else { "no" }
-            );
+            )?;
         }
 
-        eprintln!();
+        writeln!(out)?;
 
         Ok(None)
     }
 }
+
+fn history(s: &Hackshell, include_hidden: bool) -> CommandResult {
+    let records: Vec<_> = s
+        .task_history()
+        .into_iter()
+        .filter(|r| include_hidden || !r.meta.hidden)
+        .collect();
+    let mut out = s.stdout();
+
+    if records.is_empty() {
+        writeln!(s.stderr(), "No ended tasks")?;
+        return Ok(None);
+    }
+
+    writeln!(
+        out,
+        "\n{:<24} {:<24} {:<12} {:<10}",
+        "Task", "Started at", "Duration", "Outcome"
+    )?;
+    writeln!(
+        out,
+        "{:<24} {:<24} {:<12} {:<10}\n",
+        "----", "----------", "--------", "-------"
+    )?;
+
+    for record in records {
+        let duration = record.duration().to_std().unwrap_or_default();
+
+        writeln!(
+            out,
+            "{:<24} {:<24} {:<12} {:<10}",
+            record.meta.name,
+            record.meta.started.format("%Y-%m-%d %H:%M:%S"),
+            format!("{:.1?}", duration),
+            record.outcome
+        )?;
+    }
+
+    writeln!(out)?;
+
+    Ok(None)
+}
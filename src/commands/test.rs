@@ -0,0 +1,71 @@
+use crate::{Command, CommandResult, Hackshell, error::HackshellError};
+
+pub struct Test {}
+
+impl Command for Test {
+    fn commands(&self) -> &'static [&'static str] {
+        &["test", "["]
+    }
+
+    fn help(&self) -> &'static str {
+        "Evaluates a condition, failing when false. Syntax: test [!] [-n|-z] a [=|!=|-eq|-lt|... b]"
+    }
+
+    fn category(&self) -> &'static str {
+        "Shell"
+    }
+
+    fn run(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        let mut args = &cmd[1..];
+
+        if cmd[0] == "[" {
+            args = match args.split_last() {
+                Some((&"]", rest)) => rest,
+                _ => return Err("[: missing ']'".into()),
+            };
+        }
+
+        let (negate, args) = match args.split_first() {
+            Some((&"!", rest)) => (true, rest),
+            _ => (false, args),
+        };
+
+        if evaluate(args)? != negate {
+            Ok(None)
+        } else {
+            Err(HackshellError::Status {
+                code: 1,
+                message: "Condition is false".into(),
+            }
+            .into())
+        }
+    }
+}
+
+fn evaluate(args: &[&str]) -> Result<bool, String> {
+    match args {
+        [] => Ok(false),
+        [a] => Ok(!a.is_empty()),
+        ["-n", a] => Ok(!a.is_empty()),
+        ["-z", a] => Ok(a.is_empty()),
+        [a, "=" | "==", b] => Ok(a == b),
+        [a, "!=", b] => Ok(a != b),
+        [a, op @ ("-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge"), b] => {
+            let int = |v: &str| {
+                v.parse::<i64>()
+                    .map_err(|_| format!("test: integer expected: {}", v))
+            };
+            let (a, b) = (int(a)?, int(b)?);
+
+            Ok(match *op {
+                "-eq" => a == b,
+                "-ne" => a != b,
+                "-lt" => a < b,
+                "-le" => a <= b,
+                "-gt" => a > b,
+                _ => a >= b,
+            })
+        }
+        _ => Err(format!("test: unexpected expression: {}", args.join(" "))),
+    }
+}
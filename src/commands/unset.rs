@@ -1,4 +1,8 @@
-use crate::{Command, CommandResult, Hackshell};
+use crate::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, Args},
+    commands::run_with_spec,
+};
 
 pub struct Unset {}
 
@@ -15,12 +19,24 @@ impl Command for Unset {
         "Shell"
     }
 
-    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
-        if cmd.len() != 2 {
-            return Err("Syntax: unset <name>".into());
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(ArgSpec::new().arg(Arg::positional("name")))
+    }
+
+    fn complete(&self, s: &Hackshell, _args: &[&str], cursor: usize) -> Vec<String> {
+        if cursor != 1 {
+            return vec![];
         }
 
-        s.unset_var(&cmd[1]);
+        s.env().into_keys().collect()
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        run_with_spec(self, s, cmd)
+    }
+
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        s.unset_var(args.get_str("name").unwrap_or_default());
 
         Ok(None)
     }
@@ -0,0 +1,98 @@
+use std::sync::Weak;
+
+use rustyline::{
+    Context, Helper,
+    completion::Completer,
+    highlight::Highlighter,
+    hint::Hinter,
+    validate::{ValidationContext, ValidationResult, Validator},
+};
+
+use crate::{Hackshell, InnerHackshell, parser};
+
+/// Rustyline helper wired into every [`Hackshell`] editor.
+///
+/// The first word of each command of the line is completed against the registered
+/// command names, every following word is delegated to [`crate::Command::complete`].
+pub(crate) struct HackshellHelper {
+    // Weak, as the editor lives inside the shell itself.
+    shell: Weak<InnerHackshell>,
+}
+
+impl HackshellHelper {
+    pub(crate) fn new(shell: Weak<InnerHackshell>) -> Self {
+        Self { shell }
+    }
+}
+
+impl Completer for HackshellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        match self.shell.upgrade() {
+            Some(inner) => Ok(Hackshell::from_inner(inner).complete(&line[..pos])),
+            None => Ok((0, vec![])),
+        }
+    }
+}
+
+impl Hinter for HackshellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for HackshellHelper {}
+
+/// Words of the control syntax which can precede a command name.
+pub(crate) const COMMAND_KEYWORDS: &[&str] = &["if", "elif", "then", "else", "while", "do", "!"];
+
+/// Byte offset where the simple command at the end of `line` starts: after the last
+/// `|`, `;`, `&`, newline, `{` or unclosed `$(` outside of quotes.
+pub(crate) fn command_start(line: &str) -> usize {
+    let mut start = 0;
+    // Starts of the commands enclosing the open substitutions
+    let mut outer = vec![];
+    let (mut single, mut double, mut escaped) = (false, false, false);
+    let mut prev = None;
+
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else {
+            match c {
+                '\\' if !single => escaped = true,
+                '\'' if !double => single = !single,
+                '"' if !single => double = !double,
+                '(' if !single && prev == Some('$') => {
+                    outer.push(start);
+                    start = i + 1;
+                }
+                ')' if !single && !outer.is_empty() => start = outer.pop().unwrap(),
+                _ if single || double => {}
+                '|' | ';' | '&' | '\n' => start = i + 1,
+                '{' if prev != Some('$') => start = i + 1,
+                _ => {}
+            }
+        }
+
+        prev = Some(c);
+    }
+
+    start
+}
+
+/// Lines left incomplete, like `def f {`, continue on the next one.
+impl Validator for HackshellHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        match parser::parse(ctx.input()) {
+            Err(e) if parser::is_incomplete(&e) => Ok(ValidationResult::Incomplete),
+            _ => Ok(ValidationResult::Valid(None)),
+        }
+    }
+}
+
+impl Helper for HackshellHelper {}
@@ -0,0 +1,172 @@
+//! Command contexts: named command sets entered and left within a shell, like the
+//! configuration modes of network devices.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    Command, CommandEntry, Commands, Hackshell,
+    error::{HackshellError, HackshellResult},
+};
+
+#[cfg(feature = "async")]
+use crate::AsyncCommand;
+
+pub(crate) type Contexts = HashMap<String, Arc<CommandContext>>;
+
+/// Set of commands available while in a context, see [`Hackshell::enter`].
+pub struct CommandContext {
+    name: String,
+    segment: String,
+    commands: Commands,
+}
+
+impl CommandContext {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            segment: format!("({})", name),
+            commands: Default::default(),
+        }
+    }
+
+    /// Text added to the prompt while in the context, `(name)` by default.
+    pub fn segment(mut self, segment: &str) -> Self {
+        self.segment = segment.to_string();
+        self
+    }
+
+    pub fn command(mut self, command: impl Command) -> Self {
+        self.insert(CommandEntry::new(command));
+        self
+    }
+
+    #[cfg(feature = "async")]
+    pub fn async_command(mut self, command: impl AsyncCommand) -> Self {
+        self.insert(CommandEntry::new_async(command));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn insert(&mut self, ce: CommandEntry) {
+        for cmd in ce.commands().iter() {
+            self.commands.insert(cmd.to_string(), ce.clone());
+        }
+    }
+}
+
+impl Hackshell {
+    /// Register a context, replacing any context of the same name.
+    pub fn add_context(&self, context: CommandContext) -> &Self {
+        self.inner
+            .contexts
+            .write()
+            .unwrap()
+            .insert(context.name.clone(), Arc::new(context));
+
+        self
+    }
+
+    /// Names of the registered contexts.
+    pub fn contexts(&self) -> Vec<String> {
+        self.inner
+            .contexts
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Enter a context: its commands become available, taking precedence over the
+    /// ones of the contexts entered before and the global ones, and its segment is
+    /// added to the prompt. Contexts can be nested.
+    pub fn enter(&self, name: &str) -> HackshellResult<()> {
+        let context = self
+            .inner
+            .contexts
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or(HackshellError::ContextNotFound)?;
+
+        self.inner.context_stack.write().unwrap().push(context);
+
+        Ok(())
+    }
+
+    /// Leave the innermost context.
+    pub fn back(&self) -> HackshellResult<()> {
+        self.inner
+            .context_stack
+            .write()
+            .unwrap()
+            .pop()
+            .map(|_| ())
+            .ok_or_else(|| "Not in a context".into())
+    }
+
+    /// Names of the entered contexts, the innermost last.
+    pub fn context_stack(&self) -> Vec<String> {
+        self.inner
+            .context_stack
+            .read()
+            .unwrap()
+            .iter()
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    /// Prompt shown by [`Hackshell::run`]: the one set with [`Hackshell::set_prompt`],
+    /// with the segments of the entered contexts inserted before its trailing
+    /// symbols, like `router(config)(if)> `.
+    pub fn prompt(&self) -> String {
+        let prompt = self.inner.prompt.read().unwrap();
+        let stack = self.inner.context_stack.read().unwrap();
+
+        if stack.is_empty() {
+            return prompt.clone();
+        }
+
+        let (head, tail) = prompt.split_at(
+            prompt
+                .trim_end_matches(|c: char| !c.is_alphanumeric())
+                .len(),
+        );
+        let segments: String = stack.iter().map(|c| c.segment.as_str()).collect();
+
+        format!("{}{}{}", head, segments, tail)
+    }
+
+    /// Command visible as `name`, looked up from the innermost context to the
+    /// global commands.
+    pub(crate) fn find_command(&self, name: &str) -> Option<CommandEntry> {
+        self.inner
+            .context_stack
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .find_map(|c| c.commands.get(name).cloned())
+            .or_else(|| self.inner.commands.read().unwrap().get(name).cloned())
+    }
+
+    /// Every visible command by name.
+    pub(crate) fn visible_commands(&self) -> Commands {
+        let mut commands: Commands = self.inner.commands.read().unwrap().clone();
+
+        for context in self.inner.context_stack.read().unwrap().iter() {
+            commands.extend(
+                context
+                    .commands
+                    .iter()
+                    .map(|(name, c)| (name.clone(), c.clone())),
+            );
+        }
+
+        commands
+    }
+}
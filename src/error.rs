@@ -17,13 +17,41 @@ pub enum JoinError {
 pub enum HackshellError {
     String(String),
     Generic(Box<dyn std::error::Error + Send + Sync + 'static>),
+    /// Failure with a specific exit status, see [`HackshellError::status`]
+    Status {
+        code: i32,
+        message: String,
+    },
     /// Command not found
     CommandNotFound,
+    /// A command with this name is already registered, see [`crate::CollisionPolicy`]
+    CommandExists(String),
+    /// Malformed command line
+    Syntax(String),
+    /// Arguments not matching the command's [`crate::args::ArgSpec`]
+    Usage {
+        message: String,
+        usage: String,
+    },
+    /// Error raised by a line of a script run with [`crate::Hackshell::run_script`]
+    /// or [`crate::Hackshell::run_reader`]
+    Script {
+        /// Path of the script, if read from a file
+        file: Option<String>,
+        line: usize,
+        error: Box<HackshellError>,
+    },
     TaskNotFound,
+    /// No context registered with this name
+    ContextNotFound,
     /// Task is protected and cannot be terminated via the task command
     TaskIsProtected,
     /// Explicit exit error generated by command
     Exit,
+    /// Raised by `break n` to leave `n` enclosing loops
+    Break(usize),
+    /// Raised by `continue n` to resume the `n`th enclosing loop
+    Continue(usize),
     /// Ctrl-C mainly
     Interrupted,
     /// Ctrl-D
@@ -76,16 +104,51 @@ impl From<tokio::task::JoinError> for HackshellError {
     }
 }
 
+impl HackshellError {
+    /// Exit status of a command failing with this error, as stored in `$?`:
+    /// the code of [`HackshellError::Status`], 127 for unknown commands, 2 for
+    /// syntax and usage errors and 1 otherwise.
+    pub fn status(&self) -> i32 {
+        match self {
+            Self::Status { code, .. } => *code,
+            Self::CommandNotFound => 127,
+            Self::Syntax(_) | Self::Usage { .. } => 2,
+            Self::Script { error, .. } => error.status(),
+            _ => 1,
+        }
+    }
+}
+
 impl Display for HackshellError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::OtherReadline(message) => write!(f, "Readline error: {}", message),
             Self::String(message) => write!(f, "{}", message),
             Self::Generic(e) => write!(f, "{}", e),
+            Self::Status { code, message } if message.is_empty() => {
+                write!(f, "Exited with status {}", code)
+            }
+            Self::Status { message, .. } => write!(f, "{}", message),
             Self::CommandNotFound => write!(f, "Command not found"),
+            Self::CommandExists(name) => write!(f, "Command already exists: {}", name),
+            Self::Syntax(message) => write!(f, "Syntax error: {}", message),
+            Self::Usage { message, usage } => write!(f, "{}\n{}", message, usage),
+            Self::Script {
+                file: Some(file),
+                line,
+                error,
+            } => write!(f, "{}:{}: {}", file, line, error),
+            Self::Script {
+                file: None,
+                line,
+                error,
+            } => write!(f, "line {}: {}", line, error),
             Self::TaskNotFound => write!(f, "Task not found"),
+            Self::ContextNotFound => write!(f, "Context not found"),
             Self::TaskIsProtected => write!(f, "Task is protected"),
             Self::Exit => write!(f, "Shell exit"),
+            Self::Break(_) => write!(f, "Break out of a loop"),
+            Self::Continue(_) => write!(f, "Continue a loop"),
             Self::Interrupted => write!(f, "Interrupted"),
             Self::Eof => write!(f, "EOF"),
             Self::JoinError(e) => match e {
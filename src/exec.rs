@@ -0,0 +1,579 @@
+//! Execution of parsed command lines.
+//!
+//! The evaluator is written once as async code. Sync entry points drive it with
+//! [`block_on_sync`], which never suspends as sync commands are run inline.
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs::OpenOptions,
+    future::Future,
+    io::Write,
+    pin::{Pin, pin},
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    Hackshell,
+    error::{HackshellError, HackshellResult},
+    output::Output,
+    parser::{self, AndOr, Command, Connector, List, Part, Pipeline, Simple, Word},
+};
+
+/// How the simple commands are dispatched.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+    /// Through [`Hackshell::feed_string_slice`]
+    Sync,
+    /// Through [`Hackshell::feed_string_slice_async`]
+    #[cfg(feature = "async")]
+    Async,
+}
+
+/// Run a future evaluated in [`Mode::Sync`] to completion.
+pub(crate) fn block_on_sync<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+
+    match fut.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("sync evaluation never suspends"),
+    }
+}
+
+/// Errors that stop the whole line instead of being handled by `||` or `;`.
+/// `break` and `continue` only stop it up to the enclosing loop.
+pub(crate) fn is_fatal(e: &HackshellError) -> bool {
+    matches!(
+        e,
+        HackshellError::Exit
+            | HackshellError::Interrupted
+            | HackshellError::Eof
+            | HackshellError::Break(_)
+            | HackshellError::Continue(_)
+    )
+}
+
+impl Hackshell {
+    /// Run every item of the list. Returns the first error not handled by `||`,
+    /// or the output of the last item.
+    pub(crate) async fn exec_list(
+        &self,
+        list: &List,
+        mode: Mode,
+    ) -> HackshellResult<Option<String>> {
+        let mut output = None;
+        let mut first_error = None;
+
+        for item in list.items.iter() {
+            match self.exec_and_or(item, mode).await {
+                Ok(o) => output = o,
+                Err(e) if is_fatal(&e) => return Err(e),
+                Err(e) => {
+                    output = None;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(output),
+        }
+    }
+
+    async fn exec_and_or(&self, and_or: &AndOr, mode: Mode) -> HackshellResult<Option<String>> {
+        let mut result = self.exec_pipeline(&and_or.first, mode).await;
+
+        for (connector, pipeline) in and_or.rest.iter() {
+            let run = match (connector, &result) {
+                (_, Err(e)) if is_fatal(e) => return result,
+                (Connector::And, r) => r.is_ok(),
+                (Connector::Or, r) => r.is_err(),
+            };
+
+            if run {
+                result = self.exec_pipeline(pipeline, mode).await;
+            }
+        }
+
+        result
+    }
+
+    async fn exec_pipeline(
+        &self,
+        pipeline: &Pipeline,
+        mode: Mode,
+    ) -> HackshellResult<Option<String>> {
+        let result = self.exec_stages(pipeline, mode).await;
+
+        if !pipeline.negate {
+            return result;
+        }
+
+        let result = match result {
+            Ok(_) => Err(HackshellError::Status {
+                code: 1,
+                message: "Negated command succeeded".into(),
+            }),
+            Err(e) if is_fatal(&e) => Err(e),
+            Err(_) => Ok(None),
+        };
+
+        self.set_status(&result);
+        result
+    }
+
+    async fn exec_stages(
+        &self,
+        pipeline: &Pipeline,
+        mode: Mode,
+    ) -> HackshellResult<Option<String>> {
+        let mut output = None;
+        let last = pipeline.commands.len() - 1;
+
+        for (i, cmd) in pipeline.commands.iter().enumerate() {
+            let shell = self.with_input(output.take());
+
+            output = if i < last {
+                // What the command prints goes to the next one, too
+                shell.exec_captured(cmd, mode).await?
+            } else {
+                shell.exec_command(cmd, mode).await?
+            };
+        }
+
+        Ok(output)
+    }
+
+    /// Run the command, returning what it printed to [`Hackshell::stdout`] followed
+    /// by its output.
+    async fn exec_captured(&self, cmd: &Command, mode: Mode) -> HackshellResult<Option<String>> {
+        let (stdout, capture) = Output::capture();
+        let output = self.with_stdout(stdout).exec_command(cmd, mode).await?;
+
+        Ok(join_output(capture.take(), output))
+    }
+
+    async fn exec_command(&self, cmd: &Command, mode: Mode) -> HackshellResult<Option<String>> {
+        let result = self.exec_command_inner(cmd, mode).await;
+
+        self.set_status(&result);
+        result
+    }
+
+    async fn exec_command_inner(
+        &self,
+        cmd: &Command,
+        mode: Mode,
+    ) -> HackshellResult<Option<String>> {
+        match cmd {
+            Command::Simple(simple) => self.exec_simple(simple, mode).await,
+            Command::Def { name, body } => {
+                self.define_function(name, body.clone());
+                Ok(None)
+            }
+            _ => self.exec_compound(cmd, mode).await,
+        }
+    }
+
+    /// Run a conditional or a loop. Returns the output of the last command run.
+    fn exec_compound<'a>(
+        &'a self,
+        cmd: &'a Command,
+        mode: Mode,
+    ) -> Pin<Box<dyn Future<Output = HackshellResult<Option<String>>> + Send + 'a>> {
+        // Boxed, as the bodies recurse into exec_list
+        Box::pin(async move {
+            match cmd {
+                Command::If {
+                    branches,
+                    otherwise,
+                } => {
+                    for (cond, body) in branches.iter() {
+                        if self.exec_condition(cond, mode).await? {
+                            return self.exec_list(body, mode).await;
+                        }
+                    }
+
+                    match otherwise {
+                        Some(body) => self.exec_list(body, mode).await,
+                        None => Ok(None),
+                    }
+                }
+                Command::For { var, words, body } => {
+                    let items = match words {
+                        Some(words) => {
+                            let mut items = vec![];
+
+                            for word in words.iter() {
+                                items.extend(self.expand_word(word, mode).await?);
+                            }
+
+                            items
+                        }
+                        None => self.positional_args().to_vec(),
+                    };
+
+                    let shell = self.in_loop();
+                    let mut state = LoopState::default();
+
+                    for item in items {
+                        shell.set_var(var, &item);
+
+                        if !state.iteration(shell.exec_list(body, mode).await)? {
+                            break;
+                        }
+                    }
+
+                    state.finish()
+                }
+                Command::While { cond, body } => {
+                    let shell = self.in_loop();
+                    let mut state = LoopState::default();
+
+                    loop {
+                        let result = match shell.exec_condition(cond, mode).await {
+                            Ok(true) => shell.exec_list(body, mode).await,
+                            Ok(false) => break,
+                            Err(e) => Err(e),
+                        };
+
+                        if !state.iteration(result)? {
+                            break;
+                        }
+                    }
+
+                    state.finish()
+                }
+                Command::Simple(_) | Command::Def { .. } => unreachable!("not compound"),
+            }
+        })
+    }
+
+    /// Run the condition of an `if` or a `while`, which holds if it succeeds.
+    async fn exec_condition(&self, cond: &List, mode: Mode) -> HackshellResult<bool> {
+        match self.exec_list(cond, mode).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_fatal(&e) => Err(e),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Handle to the same shell, running in one more loop.
+    fn in_loop(&self) -> Self {
+        let mut s = self.clone();
+        s.invocation.loops += 1;
+        s
+    }
+
+    /// Number of loops `break` and `continue` can act on.
+    pub(crate) fn loops(&self) -> usize {
+        self.invocation.loops
+    }
+
+    /// Expand the variables and command substitutions of a word. Words made only of
+    /// unquoted expansions resulting in nothing are dropped like in POSIX shells.
+    /// The result is never split into several words, except for a lone `$@` which
+    /// gives one word per positional parameter, and for glob patterns when enabled
+    /// with [`Hackshell::set_glob_expansion`].
+    async fn expand_word(&self, word: &Word, mode: Mode) -> HackshellResult<Vec<String>> {
+        // Ignoring the empty literals left by quotes, for `"$@"`
+        let mut parts = word
+            .parts
+            .iter()
+            .filter(|p| !matches!(p, Part::Literal { text, .. } if text.is_empty()));
+
+        if let (Some(Part::Var { name, default, .. }), None) = (parts.next(), parts.next())
+            && name == "@"
+            && (default.is_none() || !self.positional_args().is_empty())
+        {
+            return Ok(self.positional_args().to_vec());
+        }
+
+        let mut text = String::new();
+        let mut keep = false;
+        // Only unquoted literal text can contain wildcards
+        let mut pattern = String::new();
+        let mut has_wildcards = false;
+
+        for part in word.parts.iter() {
+            match part {
+                Part::Literal {
+                    text: t,
+                    quoted: false,
+                } => {
+                    text.push_str(t);
+                    pattern.push_str(t);
+                    has_wildcards |= t.contains(['*', '?', '[']);
+                    keep = true;
+                }
+                Part::Literal { text: t, .. } => {
+                    text.push_str(t);
+                    pattern.push_str(&glob::Pattern::escape(t));
+                    keep = true;
+                }
+                Part::Var {
+                    name,
+                    default,
+                    quoted,
+                } => {
+                    let value = match (self.lookup_var(name).filter(|v| !v.is_empty()), default) {
+                        (Some(value), _) => value,
+                        (None, Some(default)) => self.expand_default(default, mode).await?,
+                        (None, None) => String::new(),
+                    };
+
+                    keep |= *quoted || !value.is_empty();
+                    text.push_str(&value);
+                    pattern.push_str(&glob::Pattern::escape(&value));
+                }
+                Part::Subst { list, quoted } => {
+                    let value = self.substitute(list, mode).await?;
+
+                    keep |= *quoted || !value.is_empty();
+                    text.push_str(&value);
+                    pattern.push_str(&glob::Pattern::escape(&value));
+                }
+            }
+        }
+
+        if has_wildcards && self.glob_expansion() {
+            let matches = glob_paths(&pattern);
+
+            if !matches.is_empty() {
+                return Ok(matches);
+            }
+        }
+
+        Ok(keep.then_some(text).into_iter().collect())
+    }
+
+    /// Expand the default of a `${name:-default}`, to a single string.
+    fn expand_default<'a>(
+        &'a self,
+        word: &'a Word,
+        mode: Mode,
+    ) -> Pin<Box<dyn Future<Output = HackshellResult<String>> + Send + 'a>> {
+        // Boxed, as defaults recurse into expand_word
+        Box::pin(async move { Ok(self.expand_word(word, mode).await?.join(" ")) })
+    }
+
+    /// Value of a variable, positional parameters included.
+    fn lookup_var(&self, name: &str) -> Option<String> {
+        let args = self.invocation.args.as_deref();
+
+        match name {
+            "?" => Some(self.last_status().to_string()),
+            "@" => Some(self.positional_args().join(" ")),
+            "#" => Some(self.positional_args().len().to_string()),
+            _ if name.chars().all(|c| c.is_ascii_digit()) => {
+                args?.get(name.parse::<usize>().ok()?).cloned()
+            }
+            _ => self.get_var(name),
+        }
+    }
+
+    /// Arguments of the running function, without its name.
+    fn positional_args(&self) -> &[String] {
+        self.invocation
+            .args
+            .as_deref()
+            .map(|args| &args[1..])
+            .unwrap_or_default()
+    }
+
+    /// Output of a `$(...)` substitution, without trailing newlines.
+    fn substitute<'a>(
+        &'a self,
+        list: &'a List,
+        mode: Mode,
+    ) -> Pin<Box<dyn Future<Output = HackshellResult<String>> + Send + 'a>> {
+        // Boxed, as substitutions recurse into exec_list
+        Box::pin(async move {
+            let (stdout, capture) = Output::capture();
+            let mut shell = self.with_input(None).with_stdout(stdout);
+            shell.invocation.loops = 0;
+
+            // Like in a subshell, exit only leaves the substitution
+            let output = match shell.exec_list(list, mode).await {
+                Err(HackshellError::Exit) => {
+                    return Err("exit: not allowed in a command substitution".into());
+                }
+                result => result?,
+            };
+
+            let output = join_output(capture.take(), output).unwrap_or_default();
+
+            Ok(output.trim_end_matches(['\n', '\r']).to_string())
+        })
+    }
+
+    /// Replace the command name by the alias of the same name, if any. The result
+    /// is expanded again, but an alias is never expanded twice (`alias ls='ls -l'`).
+    fn expand_aliases<'a>(&self, words: &'a [Word]) -> HackshellResult<Cow<'a, [Word]>> {
+        let mut words = Cow::Borrowed(words);
+        let mut seen = HashSet::new();
+
+        while let Some(name) = words.first().and_then(Word::as_literal) {
+            let Some(value) = self.get_alias(name) else {
+                break;
+            };
+
+            if !seen.insert(name.to_string()) {
+                break;
+            }
+
+            let mut expanded = parser::parse_words(&value)?;
+            expanded.extend_from_slice(&words[1..]);
+            words = Cow::Owned(expanded);
+        }
+
+        Ok(words)
+    }
+
+    async fn exec_simple(&self, cmd: &Simple, mode: Mode) -> HackshellResult<Option<String>> {
+        let mut words = vec![];
+
+        for word in self.expand_aliases(&cmd.words)?.iter() {
+            words.extend(self.expand_word(word, mode).await?);
+        }
+
+        let Some(redirect) = &cmd.redirect else {
+            return self.dispatch(&words, mode).await;
+        };
+
+        let path = match self.expand_word(&redirect.target, mode).await?.as_slice() {
+            [path] => path.clone(),
+            _ => return Err(HackshellError::Syntax("Ambiguous redirect".into())),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(redirect.append)
+            .truncate(!redirect.append)
+            .open(&path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut stdout = Output::new(file);
+        let output = self
+            .with_stdout(stdout.clone())
+            .dispatch(&words, mode)
+            .await?;
+
+        // The output goes to the file as well, like if it had been printed
+        if let Some(output) = output {
+            let newline = if output.ends_with('\n') { "" } else { "\n" };
+
+            write!(stdout, "{}{}", output, newline).map_err(|e| format!("{}: {}", path, e))?;
+        }
+
+        Ok(None)
+    }
+
+    async fn dispatch(&self, words: &[String], mode: Mode) -> HackshellResult<Option<String>> {
+        // Functions take precedence over commands
+        if let Some(body) = words.first().and_then(|name| self.get_function(name)) {
+            return self.call_function(body, words, mode).await;
+        }
+
+        match mode {
+            Mode::Sync => self.feed_string_slice(words),
+            #[cfg(feature = "async")]
+            Mode::Async => self.feed_string_slice_async(words).await,
+        }
+    }
+}
+
+/// Deepest nesting of function calls, to fail on infinite recursion instead of
+/// overflowing the stack.
+const MAX_CALL_DEPTH: usize = 64;
+
+impl Hackshell {
+    /// Run a function with `words` as `$0..$n`.
+    fn call_function<'a>(
+        &'a self,
+        body: Arc<List>,
+        words: &'a [String],
+        mode: Mode,
+    ) -> Pin<Box<dyn Future<Output = HackshellResult<Option<String>>> + Send + 'a>> {
+        // Boxed, as functions can call themselves
+        Box::pin(async move {
+            if self.invocation.depth >= MAX_CALL_DEPTH {
+                return Err(format!("{}: maximum call depth exceeded", words[0]).into());
+            }
+
+            let mut shell = self.clone();
+            shell.invocation.args = Some(words.into());
+            shell.invocation.depth += 1;
+            shell.invocation.loops = 0;
+
+            shell.exec_list(&body, mode).await
+        })
+    }
+}
+
+/// Paths matching a glob pattern, sorted. Wildcards do not match `/`, and hidden
+/// files are never matched.
+fn glob_paths(pattern: &str) -> Vec<String> {
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+
+    match glob::glob_with(pattern, options) {
+        Ok(paths) => paths
+            .filter_map(Result::ok)
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect(),
+        // Not a valid pattern, like `[`
+        Err(_) => vec![],
+    }
+}
+
+/// Output and errors of the iterations of a loop, which like a list returns the
+/// first error once done, or the last output.
+#[derive(Default)]
+struct LoopState {
+    output: Option<String>,
+    first_error: Option<HackshellError>,
+}
+
+impl LoopState {
+    /// Handle the result of an iteration. Returns whether the loop goes on.
+    fn iteration(&mut self, result: HackshellResult<Option<String>>) -> HackshellResult<bool> {
+        match result {
+            Ok(output) => self.output = output,
+            Err(HackshellError::Break(1)) => return Ok(false),
+            Err(HackshellError::Break(n)) => return Err(HackshellError::Break(n - 1)),
+            Err(HackshellError::Continue(1)) => {}
+            Err(HackshellError::Continue(n)) => return Err(HackshellError::Continue(n - 1)),
+            Err(e) if is_fatal(&e) => return Err(e),
+            Err(e) => {
+                self.output = None;
+                self.first_error.get_or_insert(e);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn finish(self) -> HackshellResult<Option<String>> {
+        match self.first_error {
+            Some(e) => Err(e),
+            None => Ok(self.output),
+        }
+    }
+}
+
+/// Output of a command that also printed `printed`.
+fn join_output(mut printed: String, output: Option<String>) -> Option<String> {
+    if printed.is_empty() {
+        return output;
+    }
+
+    printed.push_str(&output.unwrap_or_default());
+    Some(printed)
+}
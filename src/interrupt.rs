@@ -0,0 +1,54 @@
+//! Ctrl-C while a command is running, when the terminal isn't read by rustyline
+//! and the interrupt is a signal.
+
+use std::sync::{
+    OnceLock,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Number of live watches
+static WATCHES: AtomicUsize = AtomicUsize::new(0);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// Whether the handler could be installed
+static HANDLER: OnceLock<bool> = OnceLock::new();
+
+/// Catches Ctrl-C while alive, instead of letting it terminate the process.
+pub(crate) struct Watch;
+
+impl Watch {
+    /// Start watching. The first call installs a process-wide handler, which exits
+    /// with status 130 like the default one when nothing is watched. Returns None
+    /// if the application has installed its own handler.
+    pub(crate) fn new() -> Option<Self> {
+        let installed = *HANDLER.get_or_init(|| {
+            ctrlc::set_handler(|| {
+                if WATCHES.load(Ordering::SeqCst) == 0 {
+                    std::process::exit(130);
+                }
+
+                INTERRUPTED.store(true, Ordering::SeqCst);
+            })
+            .is_ok()
+        });
+
+        if !installed {
+            return None;
+        }
+
+        if WATCHES.fetch_add(1, Ordering::SeqCst) == 0 {
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+
+        Some(Self)
+    }
+
+    pub(crate) fn interrupted(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        WATCHES.fetch_sub(1, Ordering::SeqCst);
+    }
+}
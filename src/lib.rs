@@ -2,28 +2,63 @@
 
 use std::{
     collections::HashMap,
+    io::Write,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex, RwLock, atomic::AtomicBool},
+    sync::{
+        Arc, Mutex, RwLock, Weak,
+        atomic::{AtomicBool, AtomicI32, Ordering},
+    },
+    time::Duration,
 };
 
+use completion::HackshellHelper;
+
 #[cfg(feature = "async")]
 pub use async_trait::async_trait;
 
+#[cfg(feature = "derive")]
+pub use hackshell_derive::{Command, command};
+
 use crate::{
+    args::{ArgSpec, Args},
     error::{HackshellError, HackshellResult},
     taskpool::TaskOutput,
 };
 
+pub mod args;
 mod commands;
+mod completion;
+mod context;
 pub mod error;
+mod exec;
+mod interrupt;
+mod output;
+mod parser;
+mod script;
 pub mod taskpool;
 
 use commands::{
-    env::Env, exit::Exit, get::Get, help::Help, set::Set, sleep::Sleep, task::Task, unset::Unset,
+    alias::{Alias, Unalias},
+    context::{Back, Enter},
+    env::Env,
+    exit::Exit,
+    export::Export,
+    flow::{Break, Continue},
+    get::Get,
+    help::Help,
+    set::Set,
+    sleep::Sleep,
+    source::Source,
+    task::Task,
+    test::Test,
+    unset::Unset,
 };
-use rustyline::{DefaultEditor, error::ReadlineError};
+pub use context::CommandContext;
+pub use output::{Output, Printer};
+use rustyline::{Editor, error::ReadlineError, history::DefaultHistory};
+pub use script::OnError;
 pub use taskpool::TaskOptions;
-use taskpool::{TaskMetadata, TaskPool};
+use taskpool::{TaskLog, TaskMetadata, TaskOutcome, TaskPool, TaskRecord};
 
 pub type CommandResult =
     std::result::Result<Option<String>, Box<dyn std::error::Error + Send + Sync + 'static>>;
@@ -37,7 +72,30 @@ pub trait Command: Send + Sync + 'static {
         "User"
     }
 
+    /// Return completion candidates for the argument at index `cursor` of `args`.
+    /// `args[0]` is the command name and `args[cursor]` is the (possibly empty) word
+    /// being completed. Candidates not starting with it are filtered out by the shell.
+    fn complete(&self, _s: &Hackshell, _args: &[&str], _cursor: usize) -> Vec<String> {
+        vec![]
+    }
+
+    /// Optional argument specification. When provided, the shell validates every
+    /// invocation against it and calls [`Command::run_args`] with the parsed arguments,
+    /// failing with [`HackshellError::Usage`] on mismatch.
+    fn spec(&self) -> Option<ArgSpec> {
+        None
+    }
+
+    /// Run the command with the raw words, command name included. The shell calls it
+    /// for commands without a [`Command::spec`]; commands with one implement
+    /// [`Command::run_args`] and can parse the words with the spec here.
     fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult;
+
+    /// Run the command with arguments validated against [`Command::spec`].
+    /// Defaults to [`Command::run`] with the raw words.
+    fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        self.run(s, &args.raw())
+    }
 }
 
 /// Async version of [`Command`].
@@ -57,7 +115,23 @@ pub trait AsyncCommand: Send + Sync + 'static {
         "User"
     }
 
+    /// See [`Command::complete`].
+    fn complete(&self, _s: &Hackshell, _args: &[&str], _cursor: usize) -> Vec<String> {
+        vec![]
+    }
+
+    /// See [`Command::spec`].
+    fn spec(&self) -> Option<ArgSpec> {
+        None
+    }
+
+    /// See [`Command::run`].
     async fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult;
+
+    /// See [`Command::run_args`].
+    async fn run_args(&self, s: &Hackshell, args: &Args) -> CommandResult {
+        self.run(s, &args.raw()).await
+    }
 }
 
 #[derive(Clone)]
@@ -70,11 +144,14 @@ enum CommandInner {
 #[derive(Clone)]
 pub struct CommandEntry {
     inner: CommandInner,
+    // Built once at registration
+    spec: Option<Arc<ArgSpec>>,
 }
 
 impl CommandEntry {
     fn new(c: impl Command) -> Self {
         Self {
+            spec: c.spec().map(Arc::new),
             inner: CommandInner::Sync(Arc::new(c)),
         }
     }
@@ -82,6 +159,7 @@ impl CommandEntry {
     #[cfg(feature = "async")]
     fn new_async(c: impl AsyncCommand) -> Self {
         Self {
+            spec: c.spec().map(Arc::new),
             inner: CommandInner::Async(Arc::new(c)),
         }
     }
@@ -110,6 +188,23 @@ impl CommandEntry {
         }
     }
 
+    pub fn complete(&self, s: &Hackshell, args: &[&str], cursor: usize) -> Vec<String> {
+        match &self.inner {
+            CommandInner::Sync(c) => c.complete(s, args, cursor),
+            #[cfg(feature = "async")]
+            CommandInner::Async(c) => c.complete(s, args, cursor),
+        }
+    }
+
+    pub fn spec(&self) -> Option<&ArgSpec> {
+        self.spec.as_deref()
+    }
+
+    /// Validate `cmd` against the spec, if any.
+    fn parse(&self, cmd: &[&str]) -> HackshellResult<Option<Args>> {
+        self.spec.as_ref().map(|spec| spec.parse(cmd)).transpose()
+    }
+
     /// Check if two command entries point to the same underlying command.
     pub fn ptr_eq(&self, other: &Self) -> bool {
         match (&self.inner, &other.inner) {
@@ -122,78 +217,304 @@ impl CommandEntry {
     }
 }
 
+/// What happens when a command is added under a name already taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// The new command replaces the old one for that name
+    #[default]
+    Overwrite,
+    /// The new command is not added
+    Error,
+    /// Like [`CollisionPolicy::Overwrite`], printing a warning to [`Hackshell::stderr`]
+    Warn,
+}
+
+/// Commands of a forked shell, see [`ForkOptions`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ForkCommands {
+    /// Only the builtins
+    #[default]
+    Builtins,
+    /// A copy of the parent's commands at fork time
+    Copied,
+    /// The parent's commands, live: commands added or removed on either side are
+    /// seen by both
+    Shared,
+}
+
+/// Options for [`Hackshell::fork_with`].
+#[derive(Clone, Default)]
+pub struct ForkOptions {
+    /// If true, variables not set in the child are looked up in the parent instead
+    /// of copying its env at fork time.
+    pub inherit_env: bool,
+    pub commands: ForkCommands,
+    /// If true, the child uses the parent's task pool instead of its own.
+    pub share_tasks: bool,
+}
+
 type Commands = HashMap<String, CommandEntry>;
 type Environment = HashMap<String, String>;
+type Aliases = HashMap<String, String>;
+type Functions = HashMap<String, Arc<parser::List>>;
 
 struct InnerHackshell {
-    commands: RwLock<Commands>,
+    /// Shared with the parent with [`ForkCommands::Shared`]
+    commands: Arc<RwLock<Commands>>,
     env: RwLock<Environment>,
+    aliases: RwLock<Aliases>,
+    functions: RwLock<Functions>,
     pool: TaskPool,
     prompt: RwLock<String>,
     history_file: RwLock<Option<PathBuf>>,
-    rl: Mutex<DefaultEditor>,
+    /// Taken by the first call to `run`
+    rc_file: Mutex<Option<PathBuf>>,
+    rl: Mutex<Editor<HackshellHelper, DefaultHistory>>,
     parent: Mutex<Option<Hackshell>>,
+    children: Mutex<Vec<Weak<InnerHackshell>>>,
+    /// Look up unset variables in the parent
+    inherit_env: AtomicBool,
+    stdout: RwLock<Output>,
+    stderr: RwLock<Output>,
+    printer: output::ExternalSlot,
+    script_on_error: RwLock<OnError>,
+    last_status: AtomicI32,
+    glob_expansion: AtomicBool,
+    /// Stop [`Hackshell::attach`] on Ctrl-C
+    attach_interrupt: AtomicBool,
+    collision_policy: RwLock<CollisionPolicy>,
+    contexts: RwLock<context::Contexts>,
+    /// Entered contexts, the innermost last
+    context_stack: RwLock<Vec<Arc<CommandContext>>>,
+}
+
+/// State of a single command invocation, carried by the [`Hackshell`] handle
+/// the command is run with.
+#[derive(Clone, Default)]
+struct Invocation {
+    /// Output of the previous command of a pipeline
+    input: Option<Arc<str>>,
+    /// Redirected output
+    stdout: Option<Redirect>,
+    /// Redirected errors
+    stderr: Option<Redirect>,
+    /// Name and arguments of the running function
+    args: Option<Arc<[String]>>,
+    /// Number of nested function calls
+    depth: usize,
+    /// Number of loops the command runs in, within the current function
+    loops: usize,
+    /// Sync command run by [`Hackshell::feed_slice_async`], on a blocking thread
+    /// of the runtime
+    #[cfg(feature = "async")]
+    blocking: bool,
+}
+
+/// Redirected output, with the log of the task it was redirected in.
+#[derive(Clone)]
+struct Redirect {
+    output: Output,
+    log: Option<TaskLog>,
+}
+
+impl Redirect {
+    fn new(output: Output) -> Self {
+        Self {
+            output,
+            log: TaskLog::current(),
+        }
+    }
+
+    /// The output, unless redirected outside of the running task, e.g. when the
+    /// task was spawned by a redirected command and kept its handle.
+    fn output(&self, task: Option<&TaskLog>) -> Option<Output> {
+        match (task, &self.log) {
+            (Some(task), Some(log)) if !task.same(log) => None,
+            (Some(_), None) => None,
+            _ => Some(self.output.clone()),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Hackshell {
     inner: Arc<InnerHackshell>,
+    invocation: Invocation,
 }
 
 impl Hackshell {
+    fn from_inner(inner: Arc<InnerHackshell>) -> Self {
+        Self {
+            inner,
+            invocation: Default::default(),
+        }
+    }
+
     pub fn new(prompt: &str) -> HackshellResult<Self> {
-        let rl = DefaultEditor::new()?;
+        let s = Self::with_parts(prompt, Default::default(), Default::default())?;
+        s.add_builtins();
+        s.add_task_notifier();
+        Ok(s)
+    }
 
-        let s = Self {
-            inner: Arc::new(InnerHackshell {
-                commands: Default::default(),
+    fn with_parts(
+        prompt: &str,
+        commands: Arc<RwLock<Commands>>,
+        pool: TaskPool,
+    ) -> HackshellResult<Self> {
+        let mut rl = Editor::new()?;
+
+        let s = Self::from_inner(Arc::new_cyclic(|weak| {
+            rl.set_helper(Some(HackshellHelper::new(weak.clone())));
+
+            InnerHackshell {
+                commands,
                 env: Default::default(),
-                pool: Default::default(),
+                aliases: Default::default(),
+                functions: Default::default(),
+                pool,
                 prompt: RwLock::new(prompt.to_string()),
                 history_file: Default::default(),
+                rc_file: Default::default(),
                 rl: Mutex::new(rl),
                 parent: Mutex::new(None),
-            }),
-        };
+                children: Default::default(),
+                inherit_env: AtomicBool::new(false),
+                stdout: RwLock::new(Output::stdout()),
+                stderr: RwLock::new(Output::stderr()),
+                printer: Default::default(),
+                script_on_error: Default::default(),
+                last_status: AtomicI32::new(0),
+                glob_expansion: AtomicBool::new(false),
+                attach_interrupt: AtomicBool::new(false),
+                collision_policy: Default::default(),
+                contexts: Default::default(),
+                context_stack: Default::default(),
+            }
+        }));
 
-        s.add_command(Env {})
+        Ok(s)
+    }
+
+    fn add_builtins(&self) {
+        self.add_command(Env {})
             .add_command(Get {})
             .add_command(Set {})
             .add_command(Unset {})
+            .add_command(Export {})
             .add_command(Help {})
             .add_command(Sleep {})
+            .add_command(Source {})
+            .add_command(Alias {})
+            .add_command(Unalias {})
+            .add_command(Enter {})
+            .add_command(Back {})
+            .add_command(Test {})
+            .add_command(Break {})
+            .add_command(Continue {})
             .add_command(Exit {})
             .add_command(Task {});
-
-        Ok(s)
     }
 
     /// Create a new shell and register it as a child.
-    /// The new shell has the same env as the parent.
+    /// The new shell has the same env, aliases, functions, contexts, outputs and
+    /// options (glob expansion, collision policy, script error handling and attach
+    /// interrupt) as the parent, and is not in a context. It only has the builtin
+    /// commands, see [`Hackshell::fork_with`] to get the parent's.
     pub fn fork(&self, prompt: &str) -> HackshellResult<Self> {
-        let child = Self::new(prompt)?;
+        self.fork_with(prompt, ForkOptions::default())
+    }
 
-        // Clone the parent's env
-        {
-            let mut env = child.inner.env.write().unwrap();
-            *env = self.inner.env.read().unwrap().clone();
+    /// Like [`Hackshell::fork`], choosing what the child shares with the parent.
+    pub fn fork_with(&self, prompt: &str, opts: ForkOptions) -> HackshellResult<Self> {
+        let commands = match opts.commands {
+            ForkCommands::Builtins => Default::default(),
+            ForkCommands::Copied => {
+                Arc::new(RwLock::new(self.inner.commands.read().unwrap().clone()))
+            }
+            ForkCommands::Shared => self.inner.commands.clone(),
+        };
+        let pool = match opts.share_tasks {
+            true => self.inner.pool.clone(),
+            false => Default::default(),
+        };
+
+        let child = Self::with_parts(prompt, commands, pool)?;
+
+        if opts.commands == ForkCommands::Builtins {
+            child.add_builtins();
         }
 
+        if !opts.share_tasks {
+            child.add_task_notifier();
+        }
+
+        if opts.inherit_env {
+            child.inner.inherit_env.store(true, Ordering::Relaxed);
+        } else {
+            // Clone the parent's env
+            *child.inner.env.write().unwrap() = self.env();
+        }
+
+        *child.inner.aliases.write().unwrap() = self.aliases();
+        *child.inner.functions.write().unwrap() = self.inner.functions.read().unwrap().clone();
+        child.set_glob_expansion(self.glob_expansion());
+        child.set_attach_interrupt(self.attach_interrupt());
+        child.set_collision_policy(*self.inner.collision_policy.read().unwrap());
+        child.set_script_on_error(*self.inner.script_on_error.read().unwrap());
+        *child.inner.contexts.write().unwrap() = self.inner.contexts.read().unwrap().clone();
+
+        *child.inner.stdout.write().unwrap() = self.inner.stdout.read().unwrap().clone();
+        *child.inner.stderr.write().unwrap() = self.inner.stderr.read().unwrap().clone();
+
         *child.inner.parent.lock().unwrap() = Some(self.clone());
+        self.inner
+            .children
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&child.inner));
 
         Ok(child)
     }
 
+    /// The shell this one was forked from.
+    pub fn parent(&self) -> Option<Hackshell> {
+        self.inner.parent.lock().unwrap().clone()
+    }
+
+    /// The first shell of the fork chain, this one if it wasn't forked.
+    pub fn root(&self) -> Hackshell {
+        let mut shell = self.clone();
+
+        while let Some(parent) = shell.parent() {
+            shell = parent;
+        }
+
+        shell
+    }
+
+    /// The live shells forked from this one.
+    pub fn children(&self) -> Vec<Hackshell> {
+        let mut children = self.inner.children.lock().unwrap();
+        children.retain(|c| c.strong_count() > 0);
+
+        children
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(Self::from_inner)
+            .collect()
+    }
+
     pub fn set_history_file<P: AsRef<Path>>(&self, path: P) -> HackshellResult<()> {
         let mut hf = self.inner.history_file.write().unwrap();
         *hf = Some(path.as_ref().to_path_buf());
 
         let res = self.inner.rl.lock().unwrap().load_history(&path);
 
-        if let Err(ReadlineError::Io(ref e)) = res {
-            if matches!(e.kind(), std::io::ErrorKind::NotFound) {
-                return Ok(());
-            }
+        if let Err(ReadlineError::Io(ref e)) = res
+            && matches!(e.kind(), std::io::ErrorKind::NotFound)
+        {
+            return Ok(());
         }
 
         res?;
@@ -201,30 +522,129 @@ impl Hackshell {
         Ok(())
     }
 
+    /// Set a file of commands, like a `.hackshellrc`, run by the first call to
+    /// [`Hackshell::run`] (or [`Hackshell::run_async`]) before reading a line. Errors
+    /// are returned by that call, with the file and line they come from. A missing
+    /// file is ignored.
+    pub fn set_rc_file<P: AsRef<Path>>(&self, path: P) {
+        *self.inner.rc_file.lock().unwrap() = Some(path.as_ref().to_path_buf());
+    }
+
+    /// The rc file, if it still has to be run.
+    fn take_rc_file(&self) -> Option<PathBuf> {
+        self.inner
+            .rc_file
+            .lock()
+            .unwrap()
+            .take()
+            .filter(|path| path.exists())
+    }
+
+    /// Set what [`Hackshell::add_command`] does when a name of the new command is
+    /// already taken. Defaults to [`CollisionPolicy::Overwrite`].
+    pub fn set_collision_policy(&self, policy: CollisionPolicy) {
+        *self.inner.collision_policy.write().unwrap() = policy;
+    }
+
+    /// Register a command under all its names, according to the collision policy.
+    /// With [`CollisionPolicy::Error`], a colliding command is not added and the
+    /// error is printed to [`Hackshell::stderr`]; use [`Hackshell::try_add_command`]
+    /// to get it instead.
     pub fn add_command(&self, command: impl Command) -> &Self {
-        let ce = CommandEntry::new(command);
+        self.add_entry(CommandEntry::new(command))
+    }
 
-        for cmd in ce.commands().iter() {
-            self.inner
-                .commands
-                .write()
-                .unwrap()
-                .insert(cmd.to_string(), ce.clone());
+    #[cfg(feature = "async")]
+    pub fn add_async_command(&self, command: impl AsyncCommand) -> &Self {
+        self.add_entry(CommandEntry::new_async(command))
+    }
+
+    /// Like [`Hackshell::add_command`], returning [`HackshellError::CommandExists`] on
+    /// collision with [`CollisionPolicy::Error`].
+    pub fn try_add_command(&self, command: impl Command) -> HackshellResult<&Self> {
+        self.insert_entry(CommandEntry::new(command))?;
+        Ok(self)
+    }
+
+    #[cfg(feature = "async")]
+    pub fn try_add_async_command(&self, command: impl AsyncCommand) -> HackshellResult<&Self> {
+        self.insert_entry(CommandEntry::new_async(command))?;
+        Ok(self)
+    }
+
+    /// Register a command, removing first every command sharing one of its names,
+    /// under all their names.
+    pub fn replace_command(&self, command: impl Command) -> &Self {
+        self.replace_entry(CommandEntry::new(command))
+    }
+
+    #[cfg(feature = "async")]
+    pub fn replace_async_command(&self, command: impl AsyncCommand) -> &Self {
+        self.replace_entry(CommandEntry::new_async(command))
+    }
+
+    /// Remove the command registered as `name`, under all its names. Returns it, or
+    /// `None` if there was no such command.
+    pub fn remove_command(&self, name: &str) -> Option<CommandEntry> {
+        let mut commands = self.inner.commands.write().unwrap();
+        let entry = commands.get(name)?.clone();
+
+        commands.retain(|_, c| !c.ptr_eq(&entry));
+
+        Some(entry)
+    }
+
+    fn add_entry(&self, ce: CommandEntry) -> &Self {
+        if let Err(e) = self.insert_entry(ce) {
+            let _ = writeln!(self.stderr(), "{}", e);
         }
 
         self
     }
 
-    #[cfg(feature = "async")]
-    pub fn add_async_command(&self, command: impl AsyncCommand) -> &Self {
-        let ce = CommandEntry::new_async(command);
+    fn insert_entry(&self, ce: CommandEntry) -> HackshellResult<()> {
+        let policy = *self.inner.collision_policy.read().unwrap();
+        let mut commands = self.inner.commands.write().unwrap();
+
+        let taken: Vec<&str> = ce
+            .commands()
+            .iter()
+            .copied()
+            .filter(|name| commands.contains_key(*name))
+            .collect();
+
+        match (policy, taken.first()) {
+            (_, None) | (CollisionPolicy::Overwrite, _) => {}
+            (CollisionPolicy::Error, Some(name)) => {
+                return Err(HackshellError::CommandExists(name.to_string()));
+            }
+            (CollisionPolicy::Warn, _) => {
+                for name in taken {
+                    let _ = writeln!(self.stderr(), "Warning: command {} overwritten", name);
+                }
+            }
+        }
+
+        for cmd in ce.commands().iter() {
+            commands.insert(cmd.to_string(), ce.clone());
+        }
+
+        Ok(())
+    }
+
+    fn replace_entry(&self, ce: CommandEntry) -> &Self {
+        let mut commands = self.inner.commands.write().unwrap();
+
+        let replaced: Vec<CommandEntry> = ce
+            .commands()
+            .iter()
+            .filter_map(|name| commands.get(*name).cloned())
+            .collect();
+
+        commands.retain(|_, c| !replaced.iter().any(|r| r.ptr_eq(c)));
 
         for cmd in ce.commands().iter() {
-            self.inner
-                .commands
-                .write()
-                .unwrap()
-                .insert(cmd.to_string(), ce.clone());
+            commands.insert(cmd.to_string(), ce.clone());
         }
 
         self
@@ -267,6 +687,95 @@ impl Hackshell {
         self.inner.pool.join_async(name).await
     }
 
+    /// Call `listener` whenever a task ends, from the thread or async task that ran
+    /// it.
+    pub fn on_task_end(&self, listener: impl Fn(&TaskRecord) + Send + Sync + 'static) {
+        self.inner.pool.on_end(listener)
+    }
+
+    /// Print a notification with [`Hackshell::printer`] when a task spawned with
+    /// [`TaskOptions::notify`] ends, like `[scan] Done after 12.0s`.
+    fn add_task_notifier(&self) {
+        let inner = Arc::downgrade(&self.inner);
+
+        self.on_task_end(move |record| {
+            let Some(inner) = inner.upgrade().filter(|_| record.meta.notify) else {
+                return;
+            };
+
+            let status = match record.outcome {
+                TaskOutcome::Returned => "Done",
+                TaskOutcome::Panicked => "Panicked",
+                TaskOutcome::Killed => "Killed",
+                TaskOutcome::TimedOut => "Timed out",
+            };
+
+            let _ = Self::from_inner(inner).printer().print(&format!(
+                "[{}] {} after {:.1?}",
+                record.meta.name,
+                status,
+                record.duration().to_std().unwrap_or_default()
+            ));
+        });
+    }
+
+    /// Ended tasks, the oldest first.
+    pub fn task_history(&self) -> Vec<TaskRecord> {
+        self.inner.pool.history()
+    }
+
+    /// Set the number of ended tasks kept in the history.
+    pub fn set_task_history_capacity(&self, capacity: usize) {
+        self.inner.pool.set_history_capacity(capacity)
+    }
+
+    /// Captured output of a task, see [`TaskLog`].
+    pub fn task_log(&self, name: &str) -> Option<TaskLog> {
+        self.inner.pool.log(name)
+    }
+
+    /// Let Ctrl-C stop [`Hackshell::attach`] instead of the process. Disabled by
+    /// default, since the first attach then installs a process-wide SIGINT handler
+    /// which can't be removed: outside of an attach, Ctrl-C still exits with status
+    /// 130, but the application can no longer install its own handler. Does nothing
+    /// if it already has one.
+    pub fn set_attach_interrupt(&self, enabled: bool) {
+        self.inner
+            .attach_interrupt
+            .store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn attach_interrupt(&self) -> bool {
+        self.inner.attach_interrupt.load(Ordering::Relaxed)
+    }
+
+    /// Print the output of a task to [`Hackshell::stdout`] as it comes, starting
+    /// with the kept lines, until the task ends, or Ctrl-C is pressed if enabled
+    /// with [`Hackshell::set_attach_interrupt`].
+    pub fn attach(&self, name: &str) -> HackshellResult<()> {
+        let log = self.task_log(name).ok_or(HackshellError::TaskNotFound)?;
+        let watch = self
+            .attach_interrupt()
+            .then(interrupt::Watch::new)
+            .flatten();
+        let mut out = self.stdout();
+        let mut seen = 0;
+
+        loop {
+            let closed = log.is_closed();
+            let (lines, written) = log.wait_lines(seen, Duration::from_millis(100));
+            seen = written;
+
+            for line in lines {
+                writeln!(out, "{}", line).map_err(|e| e.to_string())?;
+            }
+
+            if closed || watch.as_ref().is_some_and(|w| w.interrupted()) {
+                return Ok(());
+            }
+        }
+    }
+
     /// Run async code from a sync context.
     ///
     /// This is useful when you have a sync [`Command`] but need to call async code.
@@ -288,11 +797,16 @@ impl Hackshell {
     /// directly, sync commands run in `spawn_blocking`.
     #[cfg(feature = "async")]
     pub async fn run_async(&self) -> HackshellResult<Option<String>> {
+        if let Some(rc) = self.take_rc_file() {
+            self.run_script_async(rc).await?;
+        }
+
         // Read line in spawn_blocking (rustyline is sync)
         let shell = self.clone();
         let line = tokio::task::spawn_blocking(move || {
             let mut rl = shell.inner.rl.lock().unwrap();
-            let readline = rl.readline(&*shell.inner.prompt.read().unwrap());
+            shell.init_printer(&mut rl);
+            let readline = rl.readline(&shell.prompt());
 
             match readline {
                 Ok(line) => {
@@ -320,18 +834,16 @@ impl Hackshell {
         self.inner.pool.get_all_filtered(include_hidden)
     }
 
+    /// Commands available in the current context, global ones included.
     pub fn get_commands(&self) -> Vec<CommandEntry> {
-        self.inner
-            .commands
-            .read()
-            .unwrap()
-            .iter()
-            .map(|c| c.1.clone())
-            .collect()
+        self.visible_commands().into_values().collect()
     }
 
+    /// Visible variables, including the inherited ones.
     pub fn env(&self) -> HashMap<String, String> {
-        self.inner.env.read().unwrap().clone()
+        let mut env = self.inherited_from().map(|p| p.env()).unwrap_or_default();
+        env.extend(self.inner.env.read().unwrap().clone());
+        env
     }
 
     pub fn get_var(&self, n: &str) -> Option<String> {
@@ -341,6 +853,23 @@ impl Hackshell {
             .unwrap()
             .get(&n.to_lowercase())
             .cloned()
+            .or_else(|| self.inherited_from()?.get_var(n))
+    }
+
+    /// Set a variable in the parent shell to its value in this one.
+    pub fn export(&self, n: &str) -> HackshellResult<()> {
+        let parent = self.parent().ok_or("No parent shell")?;
+        parent.set_var(n, &self.get_var(n).ok_or("Variable not found")?);
+        Ok(())
+    }
+
+    /// The parent, if variables are inherited from it.
+    fn inherited_from(&self) -> Option<Hackshell> {
+        if !self.inner.inherit_env.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        self.parent()
     }
 
     pub fn set_var(&self, n: &str, v: &str) {
@@ -355,20 +884,176 @@ impl Hackshell {
         self.inner.env.write().unwrap().remove(n);
     }
 
+    pub fn aliases(&self) -> HashMap<String, String> {
+        self.inner.aliases.read().unwrap().clone()
+    }
+
+    pub fn get_alias(&self, name: &str) -> Option<String> {
+        self.inner.aliases.read().unwrap().get(name).cloned()
+    }
+
+    /// Make `name` a shortcut for `value`, a command with its first arguments:
+    /// `set_alias("ll", "task --hidden")`. When running a line, a command name
+    /// matching an alias is replaced by its value before looking up the command.
+    pub fn set_alias(&self, name: &str, value: &str) -> HackshellResult<()> {
+        if name.is_empty()
+            || name
+                .chars()
+                .any(|c| c.is_whitespace() || "=|&;<>()$`'\"\\#".contains(c))
+        {
+            return Err(format!("Invalid alias name: {}", name).into());
+        }
+
+        // Only simple commands can be aliased
+        parser::parse_words(value)?;
+
+        self.inner
+            .aliases
+            .write()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+
+        Ok(())
+    }
+
+    /// Remove an alias, returning its value.
+    pub fn unset_alias(&self, name: &str) -> Option<String> {
+        self.inner.aliases.write().unwrap().remove(name)
+    }
+
+    /// Enable the expansion of unquoted arguments containing wildcards (`*.txt`,
+    /// `**/*.json`, `file?`, `[abc]`) to the sorted paths they match, hidden files
+    /// excepted. Patterns matching nothing are kept as is. Disabled by default.
+    pub fn set_glob_expansion(&self, enabled: bool) {
+        self.inner.glob_expansion.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn glob_expansion(&self) -> bool {
+        self.inner.glob_expansion.load(Ordering::Relaxed)
+    }
+
+    /// Exit status of the last command run by [`Hackshell::feed_line`], also
+    /// available as `$?`: 0 if it succeeded, else [`HackshellError::status`].
+    pub fn last_status(&self) -> i32 {
+        self.inner.last_status.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_status<T>(&self, result: &HackshellResult<T>) {
+        let status = match result {
+            Ok(_) => 0,
+            Err(e) => e.status(),
+        };
+
+        self.inner.last_status.store(status, Ordering::Relaxed);
+    }
+
+    /// Parse a line, a syntax error setting the status like a failed command.
+    pub(crate) fn parse(&self, line: &str) -> HackshellResult<parser::List> {
+        let list = parser::parse(line);
+
+        if list.is_err() {
+            self.set_status(&list);
+        }
+
+        list
+    }
+
+    /// Names of the functions defined with `def name { ... }`, sorted.
+    pub fn functions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .inner
+            .functions
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Remove a function, returning whether it existed.
+    pub fn remove_function(&self, name: &str) -> bool {
+        self.inner.functions.write().unwrap().remove(name).is_some()
+    }
+
+    pub(crate) fn define_function(&self, name: &str, body: Arc<parser::List>) {
+        self.inner
+            .functions
+            .write()
+            .unwrap()
+            .insert(name.to_string(), body);
+    }
+
+    pub(crate) fn get_function(&self, name: &str) -> Option<Arc<parser::List>> {
+        self.inner.functions.read().unwrap().get(name).cloned()
+    }
+
     pub fn set_prompt(&self, prompt: &str) {
         *self.inner.prompt.write().unwrap() = prompt.to_string();
     }
 
+    /// Compute the completions for the end of `line`, as done when pressing Tab.
+    /// Returns the byte offset where the completed word starts and the sorted candidates.
+    pub fn complete(&self, line: &str) -> (usize, Vec<String>) {
+        // Only the simple command being typed matters, e.g. `b` in `a | b`
+        let cmd_start = completion::command_start(line);
+        let start = line[cmd_start..]
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| cmd_start + i + c.len_utf8())
+            .unwrap_or(cmd_start);
+
+        let prefix = &line[start..];
+        let mut args: Vec<&str> = line[cmd_start..start].split_whitespace().collect();
+
+        while args
+            .first()
+            .is_some_and(|w| completion::COMMAND_KEYWORDS.contains(w))
+        {
+            args.remove(0);
+        }
+
+        let mut candidates = match args.first() {
+            None => self
+                .visible_commands()
+                .into_keys()
+                .chain(self.inner.aliases.read().unwrap().keys().cloned())
+                .chain(self.functions())
+                .collect(),
+            Some(name) => {
+                let command = self.find_command(name);
+
+                match command {
+                    Some(entry) => {
+                        args.push(prefix);
+                        entry.complete(self, &args, args.len() - 1)
+                    }
+                    None => vec![],
+                }
+            }
+        };
+
+        candidates.retain(|c| c.starts_with(prefix));
+        candidates.sort();
+        candidates.dedup();
+
+        (start, candidates)
+    }
+
     pub fn feed_slice(&self, cmd: &[&str]) -> HackshellResult<Option<String>> {
         if cmd.is_empty() {
             return Ok(None);
         }
 
-        let command = self.inner.commands.read().unwrap().get(cmd[0]).cloned();
+        let command = self.find_command(cmd[0]);
 
         match command {
             Some(entry) => match &entry.inner {
-                CommandInner::Sync(c) => Ok(c.run(self, cmd)?),
+                CommandInner::Sync(c) => match entry.parse(cmd)? {
+                    Some(args) => Ok(c.run_args(self, &args)?),
+                    None => Ok(c.run(self, cmd)?),
+                },
                 #[cfg(feature = "async")]
                 CommandInner::Async(_) => Err(HackshellError::AsyncCommandInSyncContext),
             },
@@ -386,22 +1071,31 @@ impl Hackshell {
             return Ok(None);
         }
 
-        let command = self.inner.commands.read().unwrap().get(cmd[0]).cloned();
+        let command = self.find_command(cmd[0]);
 
         match command {
             Some(entry) => match &entry.inner {
                 CommandInner::Sync(c) => {
+                    let args = entry.parse(cmd)?;
                     let c = c.clone();
-                    let shell = self.clone();
+                    let mut shell = self.clone();
+                    shell.invocation.blocking = true;
                     let cmd_owned: Vec<String> = cmd.iter().map(|s| s.to_string()).collect();
-                    tokio::task::spawn_blocking(move || {
-                        let cmd_refs: Vec<&str> = cmd_owned.iter().map(|s| s.as_str()).collect();
-                        c.run(&shell, &cmd_refs)
+                    tokio::task::spawn_blocking(move || match args {
+                        Some(args) => c.run_args(&shell, &args),
+                        None => {
+                            let cmd_refs: Vec<&str> =
+                                cmd_owned.iter().map(|s| s.as_str()).collect();
+                            c.run(&shell, &cmd_refs)
+                        }
                     })
                     .await?
                     .map_err(HackshellError::from)
                 }
-                CommandInner::Async(c) => Ok(c.run(self, cmd).await?),
+                CommandInner::Async(c) => match entry.parse(cmd)? {
+                    Some(args) => Ok(c.run_args(self, &args).await?),
+                    None => Ok(c.run(self, cmd).await?),
+                },
             },
             None => Err(HackshellError::CommandNotFound),
         }
@@ -417,8 +1111,8 @@ impl Hackshell {
     /// Async version of [`Hackshell::feed_line`].
     #[cfg(feature = "async")]
     pub async fn feed_line_async(&self, line: &str) -> HackshellResult<Option<String>> {
-        let cmd = shlex::Shlex::new(line).collect::<Vec<String>>();
-        self.feed_string_slice_async(&cmd).await
+        let list = self.parse(line)?;
+        self.exec_list(&list, exec::Mode::Async).await
     }
 
     pub fn feed_string_slice(&self, cmd: &[String]) -> HackshellResult<Option<String>> {
@@ -426,15 +1120,139 @@ impl Hackshell {
         self.feed_slice(&cmd_refs)
     }
 
+    /// Parse and execute a command line.
+    ///
+    /// Commands can be chained with `|`: the output returned by each command is made
+    /// available to the next one through [`Hackshell::input`].
+    ///
+    /// Pipelines can be sequenced with `;` (or newlines), `&&` (run if the previous one
+    /// succeeded) and `||` (run if the previous one failed). The output of the last
+    /// pipeline is returned, unless a pipeline failed without being handled by `||`:
+    /// then the first such error is returned once the line has been executed.
+    /// [`HackshellError::Exit`] and interruptions stop the line immediately.
+    ///
+    /// Variables are expanded right before running each command: `$name`, `${name}` and
+    /// `${name:-default}` (the expanded default, used when the variable is unset or
+    /// empty). Single quotes and `\$` keep a `$` literal. Expanded values are never
+    /// split into several arguments.
+    /// `$(cmd args)` is replaced by the output returned by the inner line, without
+    /// trailing newlines; if the inner line fails, so does the command.
+    ///
+    /// `cmd > file` and `cmd >> file` write what the command prints to
+    /// [`Hackshell::stdout`], and its output, to a file (truncated or appended).
+    /// `None` is returned then.
+    ///
+    /// `def name { cmd1; cmd2 }` defines a function, run like a command with its
+    /// arguments as `$1..$n` (`$0` being its name, `$#` their count and `$@` all of them).
+    ///
+    /// `if`/`elif`/`else`, `for` and `while` work like in POSIX shells, a condition
+    /// holding when its commands succeed. `! cmd` negates a command.
     pub fn feed_line(&self, line: &str) -> HackshellResult<Option<String>> {
-        let cmd = shlex::Shlex::new(line).collect::<Vec<String>>();
-        self.feed_string_slice(&cmd)
+        let list = self.parse(line)?;
+        exec::block_on_sync(self.exec_list(&list, exec::Mode::Sync))
+    }
+
+    /// Handle to the same shell, seeing `input` as its piped input.
+    pub(crate) fn with_input(&self, input: Option<String>) -> Self {
+        let mut s = self.clone();
+        s.invocation.input = input.map(Arc::from);
+        s
+    }
+
+    /// Handle to the same shell, on which commands print to `stdout` instead of
+    /// the shell's output, e.g. to capture the output of a single invocation.
+    pub fn with_stdout(&self, stdout: Output) -> Self {
+        let mut s = self.clone();
+        s.invocation.stdout = Some(Redirect::new(stdout));
+        s
+    }
+
+    /// Handle to the same shell, on which commands print errors to `stderr`.
+    pub fn with_stderr(&self, stderr: Output) -> Self {
+        let mut s = self.clone();
+        s.invocation.stderr = Some(Redirect::new(stderr));
+        s
+    }
+
+    /// Input piped into the running command, i.e. what the previous command of the
+    /// pipeline (`prev | cmd`) printed to [`Hackshell::stdout`] followed by its output.
+    /// `None` when not piped or when the previous command produced nothing.
+    pub fn input(&self) -> Option<&str> {
+        self.invocation.input.as_deref()
+    }
+
+    /// Set the writer commands print their output to. Defaults to the process
+    /// standard output.
+    pub fn set_stdout(&self, writer: impl Write + Send + 'static) {
+        *self.inner.stdout.write().unwrap() = Output::new(writer);
+    }
+
+    /// Set the writer commands print errors and diagnostics to. Defaults to the
+    /// process standard error.
+    pub fn set_stderr(&self, writer: impl Write + Send + 'static) {
+        *self.inner.stderr.write().unwrap() = Output::new(writer);
+    }
+
+    /// Writer commands should print their output to, instead of using `println!`.
+    /// It is the one set with [`Hackshell::set_stdout`], unless redirected by the
+    /// command line (`cmd > file`, `cmd | next`, `$(cmd)`) or [`Hackshell::with_stdout`].
+    /// Within a task, it is the task log, unless redirected in the task.
+    pub fn stdout(&self) -> Output {
+        let task = TaskLog::current();
+
+        self.invocation
+            .stdout
+            .as_ref()
+            .and_then(|r| r.output(task.as_ref()))
+            .or_else(|| task.map(Output::new))
+            .unwrap_or_else(|| self.inner.stdout.read().unwrap().clone())
+    }
+
+    /// Writer commands should print errors and diagnostics to, instead of using
+    /// `eprintln!`. It is the one set with [`Hackshell::set_stderr`], unless
+    /// overridden with [`Hackshell::with_stderr`]. Within a task, it is the task log,
+    /// unless redirected in the task.
+    pub fn stderr(&self) -> Output {
+        let task = TaskLog::current();
+
+        self.invocation
+            .stderr
+            .as_ref()
+            .and_then(|r| r.output(task.as_ref()))
+            .or_else(|| task.map(Output::new))
+            .unwrap_or_else(|| self.inner.stderr.read().unwrap().clone())
+    }
+
+    /// Writer printing above the prompt, for output of tasks and async commands
+    /// that may come while a line is being typed. See [`Printer`].
+    pub fn printer(&self) -> Printer {
+        Printer::new(
+            self.inner.printer.clone(),
+            self.inner.stdout.read().unwrap().clone(),
+        )
+    }
+
+    /// Create the rustyline printer behind [`Hackshell::printer`], if there's a
+    /// terminal.
+    fn init_printer(&self, rl: &mut Editor<HackshellHelper, DefaultHistory>) {
+        let mut printer = self.inner.printer.lock().unwrap();
+
+        if printer.is_none()
+            && let Ok(p) = rl.create_external_printer()
+        {
+            *printer = Some(Box::new(p));
+        }
     }
 
     /// Run the shell. Ask for a line and then call commands or
     pub fn run(&self) -> HackshellResult<Option<String>> {
+        if let Some(rc) = self.take_rc_file() {
+            self.run_script(rc)?;
+        }
+
         let mut rl = self.inner.rl.lock().unwrap();
-        let readline = rl.readline(&*self.inner.prompt.read().unwrap());
+        self.init_printer(&mut rl);
+        let readline = rl.readline(&self.prompt());
 
         match readline {
             Ok(line) => {
@@ -444,19 +1262,19 @@ impl Hackshell {
                 }
 
                 // Feed the line into the commands and return its value.
-                return self.feed_line(&line);
+                self.feed_line(&line)
             }
             // If Ctrl-C or Ctrl-D are pressed.
             Err(e)
                 if matches!(e, ReadlineError::Interrupted) || matches!(e, ReadlineError::Eof) =>
             {
-                return Err(e.into());
+                Err(e.into())
             }
 
             // Any other error returned from the readline method.
             Err(e) => {
-                eprintln!("{}", e);
-                return Err(e.into());
+                let _ = writeln!(self.stderr(), "{}", e);
+                Err(e.into())
             }
         }
     }
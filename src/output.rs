@@ -0,0 +1,136 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use rustyline::ExternalPrinter;
+
+/// Shared writer commands print to, obtained with [`crate::Hackshell::stdout`] and
+/// [`crate::Hackshell::stderr`].
+///
+/// Writing through it instead of using `println!` lets the shell redirect the
+/// output of a command, e.g. to a file with `cmd > file`, to the next command
+/// of a pipeline, or to any writer set with [`crate::Hackshell::set_stdout`].
+#[derive(Clone)]
+pub struct Output {
+    inner: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Output {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// The process standard output.
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+
+    /// The process standard error.
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr())
+    }
+
+    /// Output writing to a buffer, whose content can be retrieved with [`Capture::take`].
+    pub(crate) fn capture() -> (Self, Capture) {
+        let capture = Capture::default();
+        (Self::new(capture.clone()), capture)
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.lock().unwrap().flush()
+    }
+}
+
+/// Buffer behind [`Output::capture`].
+#[derive(Clone, Default)]
+pub(crate) struct Capture {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Capture {
+    pub(crate) fn take(&self) -> String {
+        let buf = std::mem::take(&mut *self.buf.lock().unwrap());
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Slot of the rustyline printer, filled by [`crate::Hackshell::run`].
+pub(crate) type ExternalSlot = Arc<Mutex<Option<Box<dyn ExternalPrinter + Send>>>>;
+
+/// Writer for messages printed while the shell may be reading a line, like the
+/// ones of tasks and async commands, obtained with [`crate::Hackshell::printer`].
+///
+/// While [`crate::Hackshell::run`] waits for input, lines are printed above the
+/// prompt, which is redrawn with the partially typed line. Before the shell runs,
+/// or without a terminal, they go to the shell stdout.
+#[derive(Clone)]
+pub struct Printer {
+    external: ExternalSlot,
+    fallback: Output,
+    /// Text not terminated by a newline yet
+    partial: Vec<u8>,
+}
+
+impl Printer {
+    pub(crate) fn new(external: ExternalSlot, fallback: Output) -> Self {
+        Self {
+            external,
+            fallback,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Print a message as its own line(s).
+    pub fn print(&self, msg: &str) -> std::io::Result<()> {
+        if let Some(external) = self.external.lock().unwrap().as_mut() {
+            return external
+                .print(format!("{}\n", msg.trim_end_matches('\n')))
+                .map_err(std::io::Error::other);
+        }
+
+        writeln!(self.fallback.clone(), "{}", msg.trim_end_matches('\n'))
+    }
+}
+
+impl Write for Printer {
+    /// Complete lines are printed as they come, the rest on flush.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.partial.extend_from_slice(buf);
+
+        if let Some(end) = self.partial.iter().rposition(|b| *b == b'\n') {
+            let lines: Vec<u8> = self.partial.drain(..=end).collect();
+            self.print(&String::from_utf8_lossy(&lines))?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.partial.is_empty() {
+            let rest = std::mem::take(&mut self.partial);
+            self.print(&String::from_utf8_lossy(&rest))?;
+        }
+
+        Ok(())
+    }
+}
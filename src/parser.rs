@@ -0,0 +1,817 @@
+//! Lexer and parser of the command line language.
+//!
+//! Quoting follows the POSIX rules implemented by `shlex`: single quotes are
+//! literal, double quotes allow `\` escapes of `$`, `` ` ``, `"` and `\`, and an
+//! unquoted `#` at the beginning of a word starts a comment.
+//!
+//! Variables (`$name`, `${name}`, `${name:-default}`) and command substitutions
+//! (`$(cmd args)`) are expanded outside of single quotes.
+//!
+//! `def name { list }` defines a function. Conditionals and loops use the POSIX
+//! syntax: `if list; then list; elif list; then list; else list; fi`,
+//! `for name in words; do list; done` and `while list; do list; done`. Keywords
+//! are unquoted words, only special at the start of a command.
+
+use std::{iter::Peekable, str::Chars, sync::Arc};
+
+use crate::error::{HackshellError, HackshellResult};
+
+/// Piece of a word, keeping track of how it was quoted.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Part {
+    Literal {
+        text: String,
+        quoted: bool,
+    },
+    Var {
+        name: String,
+        /// Expanded when the variable is unset or empty
+        default: Option<Word>,
+        quoted: bool,
+    },
+    /// `$(...)`, replaced by the output of the list
+    Subst {
+        list: List,
+        quoted: bool,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Word {
+    pub parts: Vec<Part>,
+}
+
+impl Word {
+    fn push_literal(&mut self, c: char, quoted: bool) {
+        if let Some(Part::Literal { text, quoted: q }) = self.parts.last_mut()
+            && *q == quoted
+        {
+            text.push(c);
+            return;
+        }
+
+        self.parts.push(Part::Literal {
+            text: c.to_string(),
+            quoted,
+        });
+    }
+
+    /// The text of the word if it is made of a single unquoted literal, like the
+    /// names of aliases.
+    pub(crate) fn as_literal(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [
+                Part::Literal {
+                    text,
+                    quoted: false,
+                },
+            ] => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The word as written, with quotes removed, for error messages.
+    fn describe(&self) -> String {
+        self.parts
+            .iter()
+            .map(|p| match p {
+                Part::Literal { text, .. } => text.clone(),
+                Part::Var { name, .. } => format!("${{{}}}", name),
+                Part::Subst { .. } => "$(...)".to_string(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(Word),
+    Pipe,
+    And,
+    Or,
+    Semicolon,
+    Newline,
+    /// `>`
+    Write,
+    /// `>>`
+    Append,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Word(w) => w.describe(),
+            Self::Pipe => "|".into(),
+            Self::And => "&&".into(),
+            Self::Or => "||".into(),
+            Self::Semicolon => ";".into(),
+            Self::Newline => "newline".into(),
+            Self::Write => ">".into(),
+            Self::Append => ">>".into(),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(line: &'a str) -> Self {
+        Self {
+            chars: line.chars().peekable(),
+        }
+    }
+
+    fn unterminated(what: &str) -> HackshellError {
+        HackshellError::Syntax(format!("Unterminated {}", what))
+    }
+
+    fn next_token(&mut self) -> HackshellResult<Option<Token>> {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' => {
+                    self.chars.next();
+                }
+                '#' => {
+                    // Comment until the end of the line
+                    while self.chars.next_if(|&c| c != '\n').is_some() {}
+                }
+                '\n' => {
+                    self.chars.next();
+                    return Ok(Some(Token::Newline));
+                }
+                ';' => {
+                    self.chars.next();
+                    return Ok(Some(Token::Semicolon));
+                }
+                '|' => {
+                    self.chars.next();
+
+                    if self.chars.next_if_eq(&'|').is_some() {
+                        return Ok(Some(Token::Or));
+                    }
+
+                    return Ok(Some(Token::Pipe));
+                }
+                '>' => {
+                    self.chars.next();
+
+                    if self.chars.next_if_eq(&'>').is_some() {
+                        return Ok(Some(Token::Append));
+                    }
+
+                    return Ok(Some(Token::Write));
+                }
+                '&' => {
+                    self.chars.next();
+
+                    if self.chars.next_if_eq(&'&').is_some() {
+                        return Ok(Some(Token::And));
+                    }
+
+                    return Err(HackshellError::Syntax(
+                        "Background execution with '&' is not supported".into(),
+                    ));
+                }
+                _ => return self.word().map(|w| Some(Token::Word(w))),
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn word(&mut self) -> HackshellResult<Word> {
+        let mut word = Word::default();
+
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\n' | '|' | '&' | ';' | '>' => break,
+                '\'' => {
+                    self.chars.next();
+                    self.single_quoted(&mut word)?;
+                }
+                '"' => {
+                    self.chars.next();
+                    self.double_quoted(&mut word)?;
+                }
+                '\\' => {
+                    self.chars.next();
+                    match self.chars.next() {
+                        Some('\n') => {}
+                        Some(c) => word.push_literal(c, true),
+                        None => return Err(Self::unterminated("escape")),
+                    }
+                }
+                '$' => {
+                    self.chars.next();
+                    self.dollar(&mut word, false)?;
+                }
+                _ => {
+                    self.chars.next();
+                    word.push_literal(c, false);
+                }
+            }
+        }
+
+        Ok(word)
+    }
+
+    fn single_quoted(&mut self, word: &mut Word) -> HackshellResult<()> {
+        // Keep empty quotes as an (empty) word
+        word.parts.push(Part::Literal {
+            text: String::new(),
+            quoted: true,
+        });
+
+        loop {
+            match self.chars.next() {
+                Some('\'') => return Ok(()),
+                Some(c) => word.push_literal(c, true),
+                None => return Err(Self::unterminated("single quote")),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self, word: &mut Word) -> HackshellResult<()> {
+        word.parts.push(Part::Literal {
+            text: String::new(),
+            quoted: true,
+        });
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(()),
+                Some('\\') => match self.chars.next() {
+                    Some(c @ ('$' | '`' | '"' | '\\')) => word.push_literal(c, true),
+                    Some('\n') => {}
+                    Some(c) => {
+                        word.push_literal('\\', true);
+                        word.push_literal(c, true);
+                    }
+                    None => return Err(Self::unterminated("double quote")),
+                },
+                Some('$') => self.dollar(word, true)?,
+                Some(c) => word.push_literal(c, true),
+                None => return Err(Self::unterminated("double quote")),
+            }
+        }
+    }
+}
+
+impl Lexer<'_> {
+    fn is_name_char(c: &char) -> bool {
+        c.is_ascii_alphanumeric() || *c == '_'
+    }
+
+    /// Positional parameters and `$@`, `$#`, `$?`, named by a single character.
+    fn is_special_name(c: &char) -> bool {
+        c.is_ascii_digit() || matches!(c, '@' | '#' | '?')
+    }
+
+    /// Parse what follows a `$`.
+    fn dollar(&mut self, word: &mut Word, quoted: bool) -> HackshellResult<()> {
+        if self.chars.next_if_eq(&'{').is_some() {
+            return self.braced_var(word, quoted);
+        }
+
+        if self.chars.next_if_eq(&'(').is_some() {
+            let list = parse(&self.subst_source()?)?;
+            word.parts.push(Part::Subst { list, quoted });
+            return Ok(());
+        }
+
+        let mut name = String::new();
+
+        if let Some(c) = self.chars.next_if(Self::is_special_name) {
+            // `$12` is `$1` followed by `2`, like in POSIX shells
+            name.push(c);
+        } else {
+            while let Some(c) = self.chars.next_if(Self::is_name_char) {
+                name.push(c);
+            }
+        }
+
+        if name.is_empty() {
+            // A lone `$` is literal
+            word.push_literal('$', quoted);
+        } else {
+            word.parts.push(Part::Var {
+                name,
+                default: None,
+                quoted,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Source of a command substitution, up to the matching `)`.
+    fn subst_source(&mut self) -> HackshellResult<String> {
+        let mut source = String::new();
+        let mut depth = 0;
+        let mut single = false;
+        let mut double = false;
+
+        loop {
+            let c = self.chars.next().ok_or_else(|| Self::unterminated("$("))?;
+
+            match c {
+                '\\' if !single => {
+                    source.push(c);
+                    source.extend(self.chars.next());
+                    continue;
+                }
+                '\'' if !double => single = !single,
+                '"' if !single => double = !double,
+                '(' if !single && !double => depth += 1,
+                ')' if !single && !double => {
+                    if depth == 0 {
+                        return Ok(source);
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+
+            source.push(c);
+        }
+    }
+
+    /// Parse `name}` or `name:-default}` after `${`.
+    fn braced_var(&mut self, word: &mut Word, quoted: bool) -> HackshellResult<()> {
+        let mut name = String::new();
+
+        if let Some(c) = self.chars.next_if(|c| matches!(c, '@' | '#' | '?')) {
+            name.push(c);
+        } else {
+            while let Some(c) = self.chars.next_if(Self::is_name_char) {
+                name.push(c);
+            }
+        }
+
+        if name.is_empty() {
+            return Err(HackshellError::Syntax(
+                "Bad substitution: missing name".into(),
+            ));
+        }
+
+        let default = match self.chars.next() {
+            Some('}') => None,
+            Some(':') if self.chars.next_if_eq(&'-').is_some() => {
+                // Like a word up to the closing brace, whose blanks are literal and
+                // which is never globbed, like the value of a variable
+                let mut default = Word::default();
+
+                loop {
+                    match self.chars.next() {
+                        Some('}') => break,
+                        Some('\'') => self.single_quoted(&mut default)?,
+                        Some('"') => self.double_quoted(&mut default)?,
+                        Some('\\') => match self.chars.next() {
+                            Some(c) => default.push_literal(c, true),
+                            None => return Err(Self::unterminated("${")),
+                        },
+                        Some('$') => self.dollar(&mut default, quoted)?,
+                        Some(c) => default.push_literal(c, true),
+                        None => return Err(Self::unterminated("${")),
+                    }
+                }
+
+                Some(default)
+            }
+            Some(c) => {
+                return Err(HackshellError::Syntax(format!(
+                    "Bad substitution: unexpected '{}' in ${{{}}}",
+                    c, name
+                )));
+            }
+            None => return Err(Self::unterminated("${")),
+        };
+
+        word.parts.push(Part::Var {
+            name,
+            default,
+            quoted,
+        });
+
+        Ok(())
+    }
+}
+
+/// Output redirection of a command.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Redirect {
+    /// `>>` instead of `>`
+    pub append: bool,
+    pub target: Word,
+}
+
+/// A single command with its arguments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Simple {
+    pub words: Vec<Word>,
+    /// Last `> file` or `>> file` given
+    pub redirect: Option<Redirect>,
+}
+
+/// Stage of a pipeline.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Command {
+    Simple(Simple),
+    /// `def name { list }`, defining a function
+    Def {
+        name: String,
+        body: Arc<List>,
+    },
+    /// `if cond; then body; elif cond; then body; else body; fi`
+    If {
+        branches: Vec<(List, List)>,
+        otherwise: Option<List>,
+    },
+    /// `for var in words; do body; done`, over the positional parameters without `in`
+    For {
+        var: String,
+        words: Option<Vec<Word>>,
+        body: List,
+    },
+    /// `while cond; do body; done`
+    While {
+        cond: List,
+        body: List,
+    },
+}
+
+/// Commands connected by `|`. The output of each command is the input of the next one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Pipeline {
+    /// `! pipeline`, failing if the pipeline succeeds and conversely
+    pub negate: bool,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Connector {
+    /// `&&`, run if the previous pipeline succeeded
+    And,
+    /// `||`, run if the previous pipeline failed
+    Or,
+}
+
+/// Pipelines connected by `&&` and `||`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(Connector, Pipeline)>,
+}
+
+/// Sequence of [`AndOr`]s separated by `;` or newlines.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct List {
+    pub items: Vec<AndOr>,
+}
+
+/// Message of the syntax error returned when the line ends too early, e.g. in the
+/// middle of a function definition.
+const UNEXPECTED_END: &str = "Unexpected end of line";
+
+/// Keywords ending a list, never valid as a command name.
+const CLOSING_KEYWORDS: &[&str] = &["then", "elif", "else", "fi", "do", "done"];
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    /// Number of `{` blocks being parsed, in which `}` ends commands
+    braces: usize,
+}
+
+impl Parser {
+    fn new(line: &str) -> HackshellResult<Self> {
+        let mut lexer = Lexer::new(line);
+        let mut tokens = vec![];
+
+        while let Some(token) = lexer.next_token()? {
+            tokens.push(token);
+        }
+
+        Ok(Self {
+            tokens: tokens.into_iter().peekable(),
+            braces: 0,
+        })
+    }
+
+    fn unexpected(token: Option<Token>) -> HackshellError {
+        match token {
+            Some(t) => HackshellError::Syntax(format!("Unexpected '{}'", t.describe())),
+            None => HackshellError::Syntax(UNEXPECTED_END.into()),
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.tokens.next_if_eq(&Token::Newline).is_some() {}
+    }
+
+    /// Check whether the next token is the unquoted word `keyword`.
+    fn at_keyword(&mut self, keyword: &str) -> bool {
+        matches!(self.tokens.peek(), Some(Token::Word(w)) if w.as_literal() == Some(keyword))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> HackshellResult<()> {
+        if self.at_keyword(keyword) {
+            self.tokens.next();
+            return Ok(());
+        }
+
+        match self.tokens.next() {
+            None => Err(Self::unexpected(None)),
+            Some(t) => Err(HackshellError::Syntax(format!(
+                "Expected '{}' instead of '{}'",
+                keyword,
+                t.describe()
+            ))),
+        }
+    }
+
+    /// Check whether the next token closes the current `{` block.
+    fn at_closing_brace(&mut self) -> bool {
+        self.braces > 0 && self.at_keyword("}")
+    }
+
+    fn list(&mut self) -> HackshellResult<List> {
+        self.list_until(&[])
+    }
+
+    /// List ending at the end of the line, at `}` in a block, or at one of `ends`
+    /// in command position.
+    fn list_until(&mut self, ends: &[&str]) -> HackshellResult<List> {
+        let mut list = List::default();
+
+        loop {
+            self.skip_newlines();
+
+            if self.tokens.peek().is_none()
+                || self.at_closing_brace()
+                || ends.iter().any(|end| self.at_keyword(end))
+            {
+                return Ok(list);
+            }
+
+            list.items.push(self.and_or()?);
+
+            if self.at_closing_brace() {
+                return Ok(list);
+            }
+
+            match self.tokens.next() {
+                None => return Ok(list),
+                Some(Token::Semicolon | Token::Newline) => {}
+                t => return Err(Self::unexpected(t)),
+            }
+        }
+    }
+
+    fn and_or(&mut self) -> HackshellResult<AndOr> {
+        let first = self.pipeline()?;
+        let mut rest = vec![];
+
+        loop {
+            let connector = match self.tokens.peek() {
+                Some(Token::And) => Connector::And,
+                Some(Token::Or) => Connector::Or,
+                _ => return Ok(AndOr { first, rest }),
+            };
+
+            self.tokens.next();
+            self.skip_newlines();
+            rest.push((connector, self.pipeline()?));
+        }
+    }
+
+    /// List followed by the keyword `end`, which must not be empty.
+    fn block(&mut self, end: &str) -> HackshellResult<List> {
+        let list = self.list_until(&[end])?;
+
+        if list.items.is_empty() {
+            return Err(Self::unexpected(self.tokens.next()));
+        }
+
+        self.expect_keyword(end)?;
+        Ok(list)
+    }
+
+    fn pipeline(&mut self) -> HackshellResult<Pipeline> {
+        let negate = self.at_keyword("!");
+
+        if negate {
+            self.tokens.next();
+        }
+
+        let mut pipeline = Pipeline {
+            negate,
+            commands: vec![self.command()?],
+        };
+
+        while self.tokens.next_if_eq(&Token::Pipe).is_some() {
+            self.skip_newlines();
+            pipeline.commands.push(self.command()?);
+        }
+
+        Ok(pipeline)
+    }
+
+    fn command(&mut self) -> HackshellResult<Command> {
+        let keyword = match self.tokens.peek() {
+            Some(Token::Word(w)) => w.as_literal().map(str::to_string),
+            _ => None,
+        };
+
+        match keyword.as_deref() {
+            Some("def") => {
+                self.tokens.next();
+                self.def()
+            }
+            Some("if") => {
+                self.tokens.next();
+                self.if_clause()
+            }
+            Some("for") => {
+                self.tokens.next();
+                self.for_clause()
+            }
+            Some("while") => {
+                self.tokens.next();
+                let cond = self.block("do")?;
+                let body = self.block("done")?;
+
+                Ok(Command::While { cond, body })
+            }
+            Some(k) if CLOSING_KEYWORDS.contains(&k) => Err(Self::unexpected(self.tokens.next())),
+            _ => Ok(Command::Simple(self.simple()?)),
+        }
+    }
+
+    /// `cond; then body; [elif cond; then body;]... [else body;] fi`, after `if`.
+    fn if_clause(&mut self) -> HackshellResult<Command> {
+        let mut branches = vec![];
+        let mut otherwise = None;
+
+        loop {
+            let cond = self.block("then")?;
+            let body = self.list_until(&["elif", "else", "fi"])?;
+
+            if body.items.is_empty() {
+                return Err(Self::unexpected(self.tokens.next()));
+            }
+
+            branches.push((cond, body));
+
+            if self.at_keyword("elif") {
+                self.tokens.next();
+                continue;
+            }
+
+            if self.at_keyword("else") {
+                self.tokens.next();
+                otherwise = Some(self.block("fi")?);
+            } else {
+                self.expect_keyword("fi")?;
+            }
+
+            return Ok(Command::If {
+                branches,
+                otherwise,
+            });
+        }
+    }
+
+    /// `var [in words]; do body; done`, after `for`.
+    fn for_clause(&mut self) -> HackshellResult<Command> {
+        let var = match self.tokens.next() {
+            Some(Token::Word(w)) => match w.as_literal() {
+                Some(name) if name.chars().all(|c| Lexer::is_name_char(&c)) => name.to_string(),
+                _ => {
+                    return Err(HackshellError::Syntax(format!(
+                        "Invalid variable name: {}",
+                        w.describe()
+                    )));
+                }
+            },
+            t => return Err(Self::unexpected(t)),
+        };
+
+        let mut words = None;
+
+        if self.at_keyword("in") {
+            self.tokens.next();
+            let words = words.insert(vec![]);
+
+            while let Some(Token::Word(_)) = self.tokens.peek() {
+                if let Some(Token::Word(w)) = self.tokens.next() {
+                    words.push(w);
+                }
+            }
+        }
+
+        match self.tokens.next() {
+            Some(Token::Semicolon | Token::Newline) => {}
+            t => return Err(Self::unexpected(t)),
+        }
+
+        self.skip_newlines();
+        self.expect_keyword("do")?;
+        let body = self.block("done")?;
+
+        Ok(Command::For { var, words, body })
+    }
+
+    /// `def name { list }`, after `def`.
+    fn def(&mut self) -> HackshellResult<Command> {
+        let name = match self.tokens.next() {
+            Some(Token::Word(w)) => match w.as_literal() {
+                Some(name) if name != "{" => name.to_string(),
+                _ => {
+                    return Err(HackshellError::Syntax(format!(
+                        "Invalid function name: {}",
+                        w.describe()
+                    )));
+                }
+            },
+            t => return Err(Self::unexpected(t)),
+        };
+
+        self.skip_newlines();
+        self.expect_keyword("{")?;
+
+        self.braces += 1;
+        let body = self.list()?;
+        self.braces -= 1;
+
+        self.expect_keyword("}")?;
+
+        Ok(Command::Def {
+            name,
+            body: Arc::new(body),
+        })
+    }
+
+    fn simple(&mut self) -> HackshellResult<Simple> {
+        let mut simple = Simple::default();
+
+        loop {
+            // `def f { cmd args }`
+            if self.at_closing_brace() {
+                break;
+            }
+
+            match self.tokens.peek() {
+                Some(Token::Word(_)) => {
+                    if let Some(Token::Word(w)) = self.tokens.next() {
+                        simple.words.push(w);
+                    }
+                }
+                Some(Token::Write | Token::Append) => {
+                    let append = self.tokens.next() == Some(Token::Append);
+
+                    match self.tokens.next() {
+                        Some(Token::Word(target)) => {
+                            simple.redirect = Some(Redirect { append, target });
+                        }
+                        t => return Err(Self::unexpected(t)),
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        if simple.words.is_empty() {
+            return Err(Self::unexpected(self.tokens.next()));
+        }
+
+        Ok(simple)
+    }
+}
+
+pub(crate) fn parse(line: &str) -> HackshellResult<List> {
+    Parser::new(line)?.list()
+}
+
+/// Check whether parsing failed only because the line ends too early, so that
+/// reading the next line may complete it.
+pub(crate) fn is_incomplete(e: &HackshellError) -> bool {
+    matches!(e, HackshellError::Syntax(message) if message == UNEXPECTED_END)
+}
+
+/// Parse a line made of a single command without redirection, like the value of
+/// an alias, returning its words.
+pub(crate) fn parse_words(line: &str) -> HackshellResult<Vec<Word>> {
+    let mut list = parse(line)?;
+
+    match list.items.as_mut_slice() {
+        [AndOr { first, rest }] if rest.is_empty() => match first.commands.as_mut_slice() {
+            [Command::Simple(simple)] if simple.redirect.is_none() => {
+                Ok(std::mem::take(&mut simple.words))
+            }
+            _ => Err(HackshellError::Syntax("Expected a single command".into())),
+        },
+        _ => Err(HackshellError::Syntax("Expected a single command".into())),
+    }
+}
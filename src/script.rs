@@ -0,0 +1,172 @@
+//! Non-interactive execution of files of commands.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use crate::{
+    Hackshell,
+    error::{HackshellError, HackshellResult},
+    exec::{self, Mode},
+    parser,
+};
+
+/// What a script does when one of its lines fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Stop at the first failing line and return its error
+    #[default]
+    Stop,
+    /// Print the error to [`Hackshell::stderr`] and go on with the next line. The
+    /// first error is returned once the script is done.
+    Continue,
+}
+
+impl Hackshell {
+    /// Set what scripts run by this shell do when a line fails. Defaults to
+    /// [`OnError::Stop`].
+    pub fn set_script_on_error(&self, on_error: OnError) {
+        *self.inner.script_on_error.write().unwrap() = on_error;
+    }
+
+    /// Run every line of a file through [`Hackshell::feed_line`]. Blank lines and
+    /// `#` comments are skipped, commands left incomplete at the end of a line (like
+    /// `def f {` or `cmd &&`) continue on the next one. Errors are reported as [`HackshellError::Script`]
+    /// with the file and line number, and handled according to
+    /// [`Hackshell::set_script_on_error`]. Exit and interruptions always stop the
+    /// script and are returned as is.
+    ///
+    /// Returns the output of the last line.
+    pub fn run_script<P: AsRef<Path>>(&self, path: P) -> HackshellResult<Option<String>> {
+        let (reader, file) = open(path.as_ref())?;
+        exec::block_on_sync(self.exec_script(reader, file, Mode::Sync))
+    }
+
+    /// Like [`Hackshell::run_script`], reading the lines from `reader`.
+    pub fn run_reader(&self, reader: impl BufRead + Send) -> HackshellResult<Option<String>> {
+        exec::block_on_sync(self.exec_script(reader, None, Mode::Sync))
+    }
+
+    /// [`Hackshell::run_script`] in the mode of the line running the caller, so that
+    /// sourcing a file from an async line can run async commands.
+    pub(crate) fn source<P: AsRef<Path>>(&self, path: P) -> HackshellResult<Option<String>> {
+        #[cfg(feature = "async")]
+        if self.invocation.blocking {
+            return self.block_on(self.run_script_async(path));
+        }
+
+        self.run_script(path)
+    }
+
+    /// Async version of [`Hackshell::run_script`].
+    #[cfg(feature = "async")]
+    pub async fn run_script_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> HackshellResult<Option<String>> {
+        let (reader, file) = open(path.as_ref())?;
+        self.exec_script(reader, file, Mode::Async).await
+    }
+
+    /// Async version of [`Hackshell::run_reader`].
+    #[cfg(feature = "async")]
+    pub async fn run_reader_async(
+        &self,
+        reader: impl BufRead + Send,
+    ) -> HackshellResult<Option<String>> {
+        self.exec_script(reader, None, Mode::Async).await
+    }
+
+    async fn exec_script(
+        &self,
+        reader: impl BufRead + Send,
+        file: Option<String>,
+        mode: Mode,
+    ) -> HackshellResult<Option<String>> {
+        let on_error = *self.inner.script_on_error.read().unwrap();
+        let mut output = None;
+        let mut first_error = None;
+
+        // Lines of a command spanning several ones, like a function definition
+        let mut source = String::new();
+        let mut start = 0;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| script_error(&file, i + 1, e.to_string().into()))?;
+
+            if source.is_empty() {
+                start = i + 1;
+            } else {
+                source.push('\n');
+            }
+
+            source.push_str(&line);
+
+            let result = match parser::parse(&source) {
+                Err(e) if parser::is_incomplete(&e) => continue,
+                // Blank and comment lines keep the output of the previous one
+                Ok(list) if list.items.is_empty() => {
+                    source.clear();
+                    continue;
+                }
+                Ok(list) => self.exec_list(&list, mode).await,
+                Err(e) => {
+                    let result = Err(e);
+                    self.set_status(&result);
+                    result
+                }
+            };
+
+            source.clear();
+
+            match result {
+                Ok(o) => output = o,
+                Err(e) if exec::is_fatal(&e) => return Err(e),
+                Err(e) => {
+                    let e = script_error(&file, start, e);
+
+                    if on_error == OnError::Stop {
+                        return Err(e);
+                    }
+
+                    let _ = writeln!(self.stderr(), "{}", e);
+                    output = None;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        // Still incomplete at the end of the script
+        if !source.is_empty() {
+            let e = script_error(&file, start, self.parse(&source).unwrap_err());
+
+            if on_error == OnError::Continue {
+                let _ = writeln!(self.stderr(), "{}", e);
+            }
+
+            first_error.get_or_insert(e);
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(output),
+        }
+    }
+}
+
+fn open(path: &Path) -> HackshellResult<(BufReader<File>, Option<String>)> {
+    let file = path.display().to_string();
+    let reader = File::open(path).map_err(|e| format!("{}: {}", file, e))?;
+
+    Ok((BufReader::new(reader), Some(file)))
+}
+
+fn script_error(file: &Option<String>, line: usize, error: HackshellError) -> HackshellError {
+    HackshellError::Script {
+        file: file.clone(),
+        line,
+        error: Box::new(error),
+    }
+}
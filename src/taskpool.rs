@@ -1,18 +1,33 @@
 use crate::error::{HackshellError, HackshellResult, JoinError};
 
 #[cfg(feature = "async")]
-use std::future::Future;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use std::{
     any::Any,
-    collections::HashMap,
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    io::Write,
+    panic::{self, AssertUnwindSafe},
     sync::{
-        Arc, Mutex, RwLock,
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex, RwLock, Weak,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
     },
     thread::JoinHandle,
+    time::Duration,
 };
 
+/// Number of lines kept by a task log, unless set in [`TaskOptions`].
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// Number of ended tasks kept in the history, see [`TaskPool::set_history_capacity`].
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
 /// Options for spawning a task.
 #[derive(Clone, Default)]
 pub struct TaskOptions {
@@ -20,6 +35,16 @@ pub struct TaskOptions {
     pub hidden: bool,
     /// If true, the task cannot be terminated via the task command.
     pub protected: bool,
+    /// Number of lines of output kept in the task log, [`DEFAULT_LOG_CAPACITY`] if
+    /// not set.
+    pub log_capacity: Option<usize>,
+    /// If true, the shell prints a notification when the task ends.
+    pub notify: bool,
+    /// Terminate the task if it runs longer than this, signaling a sync task or
+    /// aborting an async one.
+    pub timeout: Option<Duration>,
+    /// Terminate the task if it's still running at this time, like `timeout`.
+    pub deadline: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Clone)]
@@ -29,28 +54,312 @@ pub struct TaskMetadata {
     pub id: u64,
     pub hidden: bool,
     pub protected: bool,
+    pub notify: bool,
+    /// When the task is terminated, the earliest of the timeout and the deadline
+    pub deadline: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 pub type TaskOutput = Option<Box<dyn Any + Send>>;
 
+/// How a task ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskOutcome {
+    /// The task function returned
+    Returned,
+    /// The task function panicked
+    Panicked,
+    /// The task was terminated
+    Killed,
+    /// The task was terminated at its deadline
+    TimedOut,
+}
+
+impl std::fmt::Display for TaskOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Returned => write!(f, "returned"),
+            Self::Panicked => write!(f, "panicked"),
+            Self::Killed => write!(f, "killed"),
+            Self::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+/// Function called when a task ends, see [`TaskPool::on_end`].
+pub type EndListener = Arc<dyn Fn(&TaskRecord) + Send + Sync>;
+
+/// Ended task, kept in the history of the pool.
+#[derive(Clone)]
+pub struct TaskRecord {
+    pub meta: TaskMetadata,
+    pub ended: chrono::DateTime<chrono::Utc>,
+    pub outcome: TaskOutcome,
+    output: Arc<Mutex<TaskOutput>>,
+}
+
+impl TaskRecord {
+    pub fn duration(&self) -> chrono::TimeDelta {
+        self.ended - self.meta.started
+    }
+
+    /// Take the value returned by the task, unless it has already been taken,
+    /// here or by joining the task.
+    pub fn take_output(&self) -> TaskOutput {
+        self.output.lock().unwrap().take()
+    }
+}
+
+/// Records the end of a task in the pool, even if it panics or is aborted.
+/// Panics are caught and reported with [`Completion::panicked`] before resuming,
+/// so that the end listeners never run while unwinding.
+struct Completion {
+    pool: Weak<InnerTaskPool>,
+    meta: TaskMetadata,
+    log: TaskLog,
+    killed: Arc<AtomicBool>,
+    timed_out: Arc<AtomicBool>,
+    output: Arc<Mutex<TaskOutput>>,
+    returned: bool,
+    panicked: bool,
+    /// Dropped with the completion, telling the deadline watcher the task ended
+    _done: mpsc::Sender<()>,
+}
+
+impl Completion {
+    fn returned(mut self, output: TaskOutput) {
+        *self.output.lock().unwrap() = output;
+        self.returned = true;
+    }
+
+    fn panicked(mut self) {
+        self.panicked = true;
+    }
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        self.log.close();
+
+        let outcome = if self.panicked {
+            TaskOutcome::Panicked
+        } else if self.timed_out.load(Ordering::Relaxed) {
+            TaskOutcome::TimedOut
+        } else if self.killed.load(Ordering::Relaxed) || !self.returned {
+            TaskOutcome::Killed
+        } else {
+            TaskOutcome::Returned
+        };
+
+        if let Some(pool) = self.pool.upgrade() {
+            pool.finish(TaskRecord {
+                meta: self.meta.clone(),
+                ended: chrono::Utc::now(),
+                outcome,
+                output: self.output.clone(),
+            });
+        }
+    }
+}
+
+/// Future returning the panic of the inner one as an error.
+#[cfg(feature = "async")]
+struct CatchUnwind<F: Future>(Pin<Box<F>>);
+
+#[cfg(feature = "async")]
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.0.as_mut().poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT_LOG: RefCell<Option<TaskLog>> = const { RefCell::new(None) };
+}
+
+#[cfg(feature = "async")]
+tokio::task_local! {
+    static CURRENT_ASYNC_LOG: TaskLog;
+}
+
+/// Output of a task, keeping its last lines.
+///
+/// Within a task, [`crate::Hackshell::stdout`] and [`crate::Hackshell::stderr`]
+/// write to the task log instead of the terminal. It can also be written to
+/// directly, getting it with [`TaskLog::current`].
+#[derive(Clone)]
+pub struct TaskLog {
+    inner: Arc<(Mutex<LogBuffer>, Condvar)>,
+}
+
+struct LogBuffer {
+    lines: VecDeque<String>,
+    /// Last line, not terminated yet
+    partial: Vec<u8>,
+    capacity: usize,
+    /// Number of lines ever written
+    written: usize,
+    closed: bool,
+}
+
+impl LogBuffer {
+    fn push(&mut self, line: &[u8]) {
+        self.lines
+            .push_back(String::from_utf8_lossy(line).into_owned());
+        self.written += 1;
+
+        while self.lines.len() > self.capacity {
+            self.lines.pop_front();
+        }
+    }
+}
+
+impl TaskLog {
+    fn new(capacity: usize) -> Self {
+        let buf = LogBuffer {
+            lines: Default::default(),
+            partial: Default::default(),
+            capacity,
+            written: 0,
+            closed: false,
+        };
+
+        Self {
+            inner: Arc::new((Mutex::new(buf), Condvar::new())),
+        }
+    }
+
+    /// Whether both are the log of the same task.
+    pub(crate) fn same(&self, other: &TaskLog) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Log of the task running the caller, if any.
+    pub fn current() -> Option<Self> {
+        #[cfg(feature = "async")]
+        if let Ok(log) = CURRENT_ASYNC_LOG.try_with(|log| log.clone()) {
+            return Some(log);
+        }
+
+        CURRENT_LOG.with(|log| log.borrow().clone())
+    }
+
+    /// Kept lines, the oldest first, including an unterminated last line.
+    pub fn lines(&self) -> Vec<String> {
+        let buf = self.inner.0.lock().unwrap();
+        let mut lines: Vec<String> = buf.lines.iter().cloned().collect();
+
+        if !buf.partial.is_empty() {
+            lines.push(String::from_utf8_lossy(&buf.partial).into_owned());
+        }
+
+        lines
+    }
+
+    /// Whether the task has ended.
+    pub fn is_closed(&self) -> bool {
+        self.inner.0.lock().unwrap().closed
+    }
+
+    /// Lines written after the first `seen` ones that are still kept, waiting up
+    /// to `timeout` for one if there are none and the task is running. Returns
+    /// them with the number of lines seen afterwards.
+    pub fn wait_lines(&self, seen: usize, timeout: Duration) -> (Vec<String>, usize) {
+        let (lock, cond) = &*self.inner;
+        let mut buf = lock.lock().unwrap();
+
+        if buf.written <= seen && !buf.closed {
+            buf = cond.wait_timeout(buf, timeout).unwrap().0;
+        }
+
+        let kept_from = buf.written - buf.lines.len();
+        let lines = buf
+            .lines
+            .iter()
+            .skip(seen.saturating_sub(kept_from))
+            .cloned()
+            .collect();
+
+        (lines, buf.written)
+    }
+
+    /// Mark the task as ended, terminating the last line.
+    fn close(&self) {
+        let (lock, cond) = &*self.inner;
+        let mut buf = lock.lock().unwrap();
+
+        if !buf.partial.is_empty() {
+            let partial = std::mem::take(&mut buf.partial);
+            buf.push(&partial);
+        }
+
+        buf.closed = true;
+        cond.notify_all();
+    }
+
+    /// Run `f` with this log as the current one of the thread, which is the task's.
+    fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        CURRENT_LOG.with(|log| *log.borrow_mut() = Some(self.clone()));
+        f()
+    }
+}
+
+impl Write for TaskLog {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let (lock, cond) = &*self.inner;
+        let mut buf = lock.lock().unwrap();
+
+        for chunk in data.split_inclusive(|b| *b == b'\n') {
+            match chunk.strip_suffix(b"\n") {
+                Some(end) => {
+                    let mut line = std::mem::take(&mut buf.partial);
+                    line.extend_from_slice(end);
+                    let line = line.strip_suffix(b"\r").unwrap_or(&line).to_vec();
+                    buf.push(&line);
+                }
+                None => buf.partial.extend_from_slice(chunk),
+            }
+        }
+
+        cond.notify_all();
+
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 enum TaskInner {
     Sync {
         /// This is used to signal a sync thread to stop gracefully.
         /// In Rust, due to memory safety, it's not possible to stop normal threads, as they have no
         /// yielding points.
         run: Mutex<Option<Arc<AtomicBool>>>,
-        join_handle: Mutex<Option<JoinHandle<TaskOutput>>>,
+        join_handle: Mutex<Option<JoinHandle<()>>>,
     },
 
     #[cfg(feature = "async")]
     Async {
-        join_handle: Mutex<Option<tokio::task::JoinHandle<TaskOutput>>>,
+        join_handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
     },
 }
 
 struct Task {
     meta: TaskMetadata,
     inner: TaskInner,
+    /// Set when the task is terminated
+    killed: Arc<AtomicBool>,
+    /// Set when the task is terminated at its deadline
+    timed_out: Arc<AtomicBool>,
+    /// Filled when the task function returns
+    output: Arc<Mutex<TaskOutput>>,
 }
 
 impl Task {
@@ -59,6 +368,8 @@ impl Task {
     }
 
     fn kill(&self) -> HackshellResult<()> {
+        self.killed.store(true, Ordering::Relaxed);
+
         match &self.inner {
             TaskInner::Sync { run, .. } => {
                 if let Some(run) = run.lock().unwrap().take() {
@@ -86,7 +397,7 @@ impl Task {
 
                 wh.join().map_err(|e| {
                     HackshellError::JoinError(JoinError::Sync(Box::new(Mutex::new(e))))
-                })
+                })?;
             }
 
             #[cfg(feature = "async")]
@@ -102,9 +413,11 @@ impl Task {
                 handle.block_on(async move {
                     wh.await
                         .map_err(|e| HackshellError::JoinError(JoinError::Async(e)))
-                })
+                })?;
             }
         }
+
+        Ok(self.output.lock().unwrap().take())
     }
 
     #[cfg(feature = "async")]
@@ -119,16 +432,37 @@ impl Task {
                     .ok_or(HackshellError::JoinError(JoinError::AlreadyJoining))?;
 
                 wh.await
-                    .map_err(|e| HackshellError::JoinError(JoinError::Async(e)))
+                    .map_err(|e| HackshellError::JoinError(JoinError::Async(e)))?;
+
+                Ok(self.output.lock().unwrap().take())
             }
         }
     }
 }
 
-#[derive(Default)]
 struct InnerTaskPool {
     task_id: Arc<AtomicU64>,
     tasks: RwLock<HashMap<String, Task>>,
+    /// Logs of the running tasks and of those in the history, by name, with the
+    /// id of the task
+    logs: RwLock<HashMap<String, (u64, TaskLog)>>,
+    /// Ended tasks, the oldest first
+    history: Mutex<VecDeque<TaskRecord>>,
+    history_capacity: AtomicUsize,
+    listeners: RwLock<Vec<EndListener>>,
+}
+
+impl Default for InnerTaskPool {
+    fn default() -> Self {
+        Self {
+            task_id: Default::default(),
+            tasks: Default::default(),
+            logs: Default::default(),
+            history: Default::default(),
+            history_capacity: AtomicUsize::new(DEFAULT_HISTORY_CAPACITY),
+            listeners: Default::default(),
+        }
+    }
 }
 
 impl InnerTaskPool {
@@ -161,6 +495,64 @@ impl InnerTaskPool {
 
         Ok(())
     }
+
+    /// Terminate a task that reached its deadline.
+    fn time_out(&self, id: u64) {
+        if let Some(task) = self
+            .tasks
+            .read()
+            .unwrap()
+            .values()
+            .find(|t| t.meta.id == id)
+        {
+            task.timed_out.store(true, Ordering::Relaxed);
+        }
+
+        let _ = self.remove_by_id(id);
+    }
+
+    /// Remove an ended task, add it to the history and tell the listeners.
+    fn finish(&self, record: TaskRecord) {
+        {
+            let mut tasks = self.tasks.write().unwrap();
+
+            if tasks
+                .get(&record.meta.name)
+                .is_some_and(|t| t.meta.id == record.meta.id)
+            {
+                tasks.remove(&record.meta.name);
+            }
+        }
+
+        {
+            let mut history = self.history.lock().unwrap();
+            history.push_back(record.clone());
+            self.trim_history(&mut history);
+        }
+
+        let listeners = self.listeners.read().unwrap().clone();
+
+        for listener in listeners {
+            listener(&record);
+        }
+    }
+
+    /// Drop the oldest records beyond the capacity, with their logs.
+    fn trim_history(&self, history: &mut VecDeque<TaskRecord>) {
+        while history.len() > self.history_capacity.load(Ordering::Relaxed) {
+            let Some(record) = history.pop_front() else {
+                break;
+            };
+            let mut logs = self.logs.write().unwrap();
+
+            if logs
+                .get(&record.meta.name)
+                .is_some_and(|(id, _)| *id == record.meta.id)
+            {
+                logs.remove(&record.meta.name);
+            }
+        }
+    }
 }
 
 impl Drop for InnerTaskPool {
@@ -192,39 +584,45 @@ impl TaskPool {
     {
         let run = Arc::new(AtomicBool::new(true));
         let run_ref = run.clone();
-        let weak_inner = Arc::downgrade(&self.inner);
-        let name = name.to_string();
-
-        // There could or could not be the task with the same name.
-        // In the case it's there, we kill it and insert the new one.
-        let _ = self.remove(&name);
-        let id = self.gen_task_id();
+        let (completion, done) = self.prepare(name, &opts);
+        let log = completion.log.clone();
+        let (meta, killed, timed_out, output) = (
+            completion.meta.clone(),
+            completion.killed.clone(),
+            completion.timed_out.clone(),
+            completion.output.clone(),
+        );
+
+        // Held until the task is inserted, so that it's not removed by its
+        // completion before.
+        let mut tasks = self.inner.tasks.write().unwrap();
 
         let handle = std::thread::spawn(move || {
-            let ret = func(run_ref);
+            let ret = panic::catch_unwind(AssertUnwindSafe(|| log.scope(|| func(run_ref))));
 
             // Automatic removal once it's finished (if pool still exists)
-            if let Some(inner) = weak_inner.upgrade() {
-                let _ = inner.remove_by_id(id);
+            match ret {
+                Ok(ret) => completion.returned(ret),
+                Err(payload) => {
+                    completion.panicked();
+                    panic::resume_unwind(payload);
+                }
             }
-            ret
         });
 
         let task = Task {
-            meta: TaskMetadata {
-                name: name.clone(),
-                started: chrono::Utc::now(),
-                id,
-                hidden: opts.hidden,
-                protected: opts.protected,
-            },
+            meta,
             inner: TaskInner::Sync {
                 run: Mutex::new(Some(run)),
                 join_handle: Mutex::new(Some(handle)),
             },
+            killed,
+            timed_out,
+            output,
         };
 
-        self.inner.tasks.write().unwrap().insert(name, task);
+        self.watch_deadline(&task.meta, done);
+        tasks.insert(name.to_string(), task);
     }
 
     #[cfg(feature = "async")]
@@ -240,40 +638,167 @@ impl TaskPool {
     where
         F: Future<Output = TaskOutput> + Send + 'static,
     {
-        let _ = self.remove(name);
-        let id = self.gen_task_id();
-        let weak_inner = Arc::downgrade(&self.inner);
-        let name = name.to_string();
+        // The deadline is watched by the task itself, not by a thread
+        let (completion, _) = self.prepare(name, &opts);
+        let log = completion.log.clone();
+        let (meta, killed, timed_out, output) = (
+            completion.meta.clone(),
+            completion.killed.clone(),
+            completion.timed_out.clone(),
+            completion.output.clone(),
+        );
+        let deadline = meta.deadline.map(|deadline| {
+            tokio::time::Instant::now()
+                + (deadline - chrono::Utc::now()).to_std().unwrap_or_default()
+        });
 
-        let handle: tokio::task::JoinHandle<TaskOutput> = tokio::spawn(async move {
-            let res = func.await;
-            // Automatic removal once it's finished (if pool still exists)
-            if let Some(inner) = weak_inner.upgrade() {
-                let _ = inner.remove_by_id(id);
+        let mut tasks = self.inner.tasks.write().unwrap();
+
+        let handle: tokio::task::JoinHandle<()> = tokio::spawn(async move {
+            let body = CatchUnwind(Box::pin(CURRENT_ASYNC_LOG.scope(log, func)));
+            let res = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, body).await {
+                    Ok(res) => res,
+                    Err(_) => {
+                        completion.timed_out.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                },
+                None => body.await,
+            };
+            // Automatic removal once it's finished (if pool still exists), also
+            // when aborted, by dropping the completion
+            match res {
+                Ok(res) => completion.returned(res),
+                Err(payload) => {
+                    completion.panicked();
+                    panic::resume_unwind(payload);
+                }
             }
-            res
         });
 
         let task = Task {
-            meta: TaskMetadata {
-                name: name.clone(),
-                started: chrono::Utc::now(),
-                id,
-                hidden: opts.hidden,
-                protected: opts.protected,
-            },
+            meta,
             inner: TaskInner::Async {
                 join_handle: Mutex::new(Some(handle)),
             },
+            killed,
+            timed_out,
+            output,
         };
 
-        self.inner.tasks.write().unwrap().insert(name, task);
+        tasks.insert(name.to_string(), task);
     }
 
     fn remove_by_id(&self, id: u64) -> HackshellResult<()> {
         self.inner.remove_by_id(id)
     }
 
+    /// Completion of a new task, replacing the one with the same name, and the
+    /// receiver telling when it's dropped.
+    fn prepare(&self, name: &str, opts: &TaskOptions) -> (Completion, mpsc::Receiver<()>) {
+        // There could or could not be the task with the same name.
+        // In the case it's there, we kill it and insert the new one.
+        let _ = self.remove(name);
+
+        let started = chrono::Utc::now();
+        let timeout = opts
+            .timeout
+            .and_then(|t| chrono::TimeDelta::from_std(t).ok())
+            .and_then(|t| started.checked_add_signed(t));
+        let (done, done_rx) = mpsc::channel();
+
+        let id = self.gen_task_id();
+        let completion = Completion {
+            pool: Arc::downgrade(&self.inner),
+            meta: TaskMetadata {
+                name: name.to_string(),
+                started,
+                id,
+                hidden: opts.hidden,
+                protected: opts.protected,
+                notify: opts.notify,
+                deadline: timeout.into_iter().chain(opts.deadline).min(),
+            },
+            log: self.new_log(name, id, opts),
+            killed: Default::default(),
+            timed_out: Default::default(),
+            output: Default::default(),
+            returned: false,
+            panicked: false,
+            _done: done,
+        };
+
+        (completion, done_rx)
+    }
+
+    /// Terminate the sync task at its deadline, unless it has ended before.
+    fn watch_deadline(&self, meta: &TaskMetadata, done: mpsc::Receiver<()>) {
+        let Some(deadline) = meta.deadline else {
+            return;
+        };
+        let pool = Arc::downgrade(&self.inner);
+        let id = meta.id;
+
+        std::thread::spawn(move || {
+            let remaining = (deadline - chrono::Utc::now()).to_std().unwrap_or_default();
+
+            if let Err(mpsc::RecvTimeoutError::Timeout) = done.recv_timeout(remaining)
+                && let Some(pool) = pool.upgrade()
+            {
+                pool.time_out(id);
+            }
+        });
+    }
+
+    fn new_log(&self, name: &str, id: u64, opts: &TaskOptions) -> TaskLog {
+        let log = TaskLog::new(opts.log_capacity.unwrap_or(DEFAULT_LOG_CAPACITY));
+
+        self.inner
+            .logs
+            .write()
+            .unwrap()
+            .insert(name.to_string(), (id, log.clone()));
+
+        log
+    }
+
+    /// Log of the task, kept after it ends while the task is in the history, until
+    /// another task with the same name is spawned.
+    pub fn log(&self, name: &str) -> Option<TaskLog> {
+        self.inner
+            .logs
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|(_, log)| log.clone())
+    }
+
+    /// Call `listener` whenever a task ends, from the thread or async task that ran
+    /// it.
+    pub fn on_end(&self, listener: impl Fn(&TaskRecord) + Send + Sync + 'static) {
+        self.inner
+            .listeners
+            .write()
+            .unwrap()
+            .push(Arc::new(listener));
+    }
+
+    /// Ended tasks, the oldest first.
+    pub fn history(&self) -> Vec<TaskRecord> {
+        self.inner.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Set the number of ended tasks kept in the history,
+    /// [`DEFAULT_HISTORY_CAPACITY`] by default.
+    pub fn set_history_capacity(&self, capacity: usize) {
+        self.inner
+            .history_capacity
+            .store(capacity, Ordering::Relaxed);
+        self.inner
+            .trim_history(&mut self.inner.history.lock().unwrap());
+    }
+
     pub fn remove(&self, name: &str) -> HackshellResult<()> {
         let (_, task) = self
             .inner
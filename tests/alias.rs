@@ -0,0 +1,149 @@
+use hackshell::{Hackshell, error::HackshellError};
+
+mod common;
+
+use common::{Buffer, Echo};
+
+fn shell() -> Hackshell {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Echo);
+    shell
+}
+
+#[test]
+fn test_alias_expansion() {
+    let shell = shell();
+
+    shell.feed_line("alias hi='echo hello'").unwrap();
+    assert_eq!(shell.get_alias("hi"), Some("echo hello".to_string()));
+
+    assert_eq!(
+        shell.feed_line("hi world").unwrap(),
+        Some("hello world".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("echo a | hi; hi && hi b").unwrap(),
+        Some("hello b".to_string())
+    );
+
+    // Only command names are expanded, and not when quoted
+    assert_eq!(shell.feed_line("echo hi").unwrap(), Some("hi".to_string()));
+    assert!(matches!(
+        shell.feed_line("'hi'"),
+        Err(HackshellError::CommandNotFound)
+    ));
+}
+
+#[test]
+fn test_alias_variables_expanded_when_run() {
+    let shell = shell();
+
+    shell.feed_line(r#"alias tgt='echo "$target"'"#).unwrap();
+
+    shell.set_var("target", "10.0.0.1");
+    assert_eq!(
+        shell.feed_line("tgt").unwrap(),
+        Some("10.0.0.1".to_string())
+    );
+
+    shell.set_var("target", "10.0.0.2");
+    assert_eq!(
+        shell.feed_line("tgt").unwrap(),
+        Some("10.0.0.2".to_string())
+    );
+}
+
+#[test]
+fn test_recursive_aliases() {
+    let shell = shell();
+
+    shell.set_alias("echo", "echo -n").unwrap();
+    shell.set_alias("e", "echo x").unwrap();
+    shell.set_alias("loop1", "loop2").unwrap();
+    shell.set_alias("loop2", "loop1").unwrap();
+
+    assert_eq!(shell.feed_line("e y").unwrap(), Some("-n x y".to_string()));
+    assert!(matches!(
+        shell.feed_line("loop1"),
+        Err(HackshellError::CommandNotFound)
+    ));
+}
+
+#[test]
+fn test_alias_listing() {
+    let shell = shell();
+    let stdout = Buffer::default();
+    shell.set_stdout(stdout.clone());
+
+    shell
+        .feed_line(r#"alias b='echo "it'\''s"' a='echo a'"#)
+        .unwrap();
+    assert_eq!(shell.get_alias("b"), Some(r#"echo "it's""#.to_string()));
+
+    shell.feed_line("alias").unwrap();
+    assert_eq!(
+        stdout.take(),
+        "alias a='echo a'\nalias b='echo \"it'\\''s\"'\n"
+    );
+
+    // The listing can be fed back
+    shell.feed_line("alias b").unwrap();
+    shell.feed_line(&stdout.take()).unwrap();
+    assert_eq!(shell.get_alias("b"), Some(r#"echo "it's""#.to_string()));
+
+    assert!(shell.feed_line("alias nope").is_err());
+}
+
+#[test]
+fn test_unalias() {
+    let shell = shell();
+
+    shell.feed_line("alias a=echo b=echo c=echo").unwrap();
+    shell.feed_line("unalias a").unwrap();
+
+    assert_eq!(shell.get_alias("a"), None);
+    assert!(shell.feed_line("unalias a").is_err());
+    assert!(shell.feed_line("unalias").is_err());
+
+    shell.feed_line("unalias -a").unwrap();
+    assert!(shell.aliases().is_empty());
+}
+
+#[test]
+fn test_invalid_aliases() {
+    let shell = shell();
+
+    for line in [
+        "alias =echo",
+        "alias 'a b=echo'",
+        "alias 'a=echo; echo'",
+        "alias 'a=echo | echo'",
+        "alias 'a=echo > file'",
+        "alias a=",
+    ] {
+        assert!(shell.feed_line(line).is_err(), "{}", line);
+    }
+
+    assert!(shell.aliases().is_empty());
+}
+
+#[test]
+fn test_fork_inherits_aliases() {
+    let shell = shell();
+    shell.set_alias("hi", "echo hello").unwrap();
+
+    let child = shell.fork("child> ").unwrap();
+    child.set_alias("bye", "echo bye").unwrap();
+
+    assert_eq!(child.get_alias("hi"), Some("echo hello".to_string()));
+    assert_eq!(shell.get_alias("bye"), None);
+}
+
+#[test]
+fn test_alias_completion() {
+    let shell = shell();
+    shell.set_alias("hi", "echo hello").unwrap();
+
+    assert_eq!(shell.complete("h").1, vec!["help", "hi"]);
+    assert_eq!(shell.complete("unalias ").1, vec!["hi"]);
+}
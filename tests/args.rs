@@ -0,0 +1,215 @@
+use std::sync::{Arc, Mutex};
+
+use hackshell::{
+    Command, CommandResult, Hackshell,
+    args::{Arg, ArgSpec, ArgType, Args},
+    error::HackshellError,
+};
+
+fn scan_spec() -> ArgSpec {
+    ArgSpec::new()
+        .arg(Arg::positional("host"))
+        .arg(
+            Arg::positional("ports")
+                .value_type(ArgType::Uint)
+                .variadic()
+                .optional(),
+        )
+        .arg(
+            Arg::option("timeout")
+                .short('t')
+                .value_type(ArgType::Float)
+                .default("1.5"),
+        )
+        .arg(Arg::option("mode").short('m').required())
+        .arg(Arg::flag("verbose").short('v'))
+}
+
+#[test]
+fn test_parse_positionals_options_and_flags() {
+    let spec = scan_spec();
+
+    let args = spec
+        .parse(&[
+            "scan",
+            "-v",
+            "--mode=syn",
+            "10.0.0.1",
+            "22",
+            "80",
+            "-t",
+            "3",
+        ])
+        .unwrap();
+
+    assert_eq!(args.command(), "scan");
+    assert_eq!(args.get_str("host"), Some("10.0.0.1"));
+    assert_eq!(args.get_all("ports"), vec!["22", "80"]);
+    assert_eq!(args.get::<f64>("timeout"), Some(3.0));
+    assert_eq!(args.get_str("mode"), Some("syn"));
+    assert!(args.flag("verbose"));
+    assert_eq!(args.raw().len(), 8);
+}
+
+#[test]
+fn test_parse_defaults_and_optionals() {
+    let spec = scan_spec();
+
+    let args = spec.parse(&["scan", "host", "-m", "connect"]).unwrap();
+
+    assert_eq!(args.get::<f64>("timeout"), Some(1.5));
+    assert!(!args.contains("ports"));
+    assert!(args.get_all("ports").is_empty());
+    assert!(!args.flag("verbose"));
+}
+
+#[test]
+fn test_parse_double_dash_ends_options() {
+    let spec = ArgSpec::new().arg(Arg::positional("value"));
+
+    let args = spec.parse(&["echo", "--", "-v"]).unwrap();
+    assert_eq!(args.get_str("value"), Some("-v"));
+}
+
+#[test]
+fn test_parse_errors() {
+    let spec = scan_spec();
+
+    let cases: &[&[&str]] = &[
+        // Missing positional
+        &["scan", "-m", "syn"],
+        // Missing required option
+        &["scan", "host"],
+        // Missing option value
+        &["scan", "host", "-m"],
+        // Wrong type
+        &["scan", "host", "-m", "syn", "http"],
+        &["scan", "host", "-m", "syn", "-t", "soon"],
+        // Unknown option, taken as a port
+        &["scan", "host", "-m", "syn", "--fast"],
+        // Value given to a flag
+        &["scan", "host", "-m", "syn", "--verbose=yes"],
+    ];
+
+    for case in cases {
+        match spec.parse(case) {
+            Err(HackshellError::Usage { usage, .. }) => {
+                assert_eq!(usage, "Usage: scan [OPTIONS] <host> [ports]...");
+            }
+            other => panic!("{:?} parsed as {:?}", case, other.map(|_| ())),
+        }
+    }
+}
+
+#[test]
+fn test_parse_unexpected_argument() {
+    let spec = ArgSpec::new().arg(Arg::positional("name"));
+
+    let err = spec.parse(&["get", "a", "b"]).err().unwrap();
+    assert_eq!(err.to_string(), "Unexpected argument: b\nUsage: get <name>");
+}
+
+#[test]
+fn test_help_text() {
+    let text = scan_spec().help_text("scan");
+
+    assert!(text.starts_with("Usage: scan [OPTIONS] <host> [ports]...\n"));
+    assert!(text.contains("\nArguments:\n"));
+    assert!(text.contains("\nOptions:\n"));
+    assert!(text.contains("-t, --timeout <timeout>"));
+    assert!(text.contains("[default: 1.5]"));
+}
+
+struct Greet {
+    seen: Arc<Mutex<Vec<String>>>,
+}
+
+impl Command for Greet {
+    fn commands(&self) -> &'static [&'static str] {
+        &["greet"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Greets someone"
+    }
+
+    fn spec(&self) -> Option<ArgSpec> {
+        Some(
+            ArgSpec::new().arg(Arg::positional("name")).arg(
+                Arg::option("times")
+                    .short('n')
+                    .value_type(ArgType::Uint)
+                    .default("1"),
+            ),
+        )
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        self.run_args(s, &self.spec().unwrap().parse(cmd)?)
+    }
+
+    fn run_args(&self, _s: &Hackshell, args: &Args) -> CommandResult {
+        let name = args.get_str("name").unwrap();
+        let times = args.get::<usize>("times").unwrap();
+
+        self.seen.lock().unwrap().push(name.repeat(times));
+
+        Ok(Some(name.to_string()))
+    }
+}
+
+#[test]
+fn test_feed_validates_against_spec() {
+    let shell = Hackshell::new("> ").unwrap();
+    let seen = Arc::new(Mutex::new(vec![]));
+
+    shell.add_command(Greet { seen: seen.clone() });
+
+    assert_eq!(
+        shell.feed_line("greet bob -n 2").unwrap(),
+        Some("bob".to_string())
+    );
+    assert!(matches!(
+        shell.feed_line("greet"),
+        Err(HackshellError::Usage { .. })
+    ));
+    assert!(matches!(
+        shell.feed_line("greet bob -n many"),
+        Err(HackshellError::Usage { .. })
+    ));
+
+    // Only the valid invocation reached the command
+    assert_eq!(*seen.lock().unwrap(), vec!["bobbob".to_string()]);
+}
+
+#[test]
+fn test_builtins_report_usage() {
+    let shell = Hackshell::new("> ").unwrap();
+
+    for line in ["get", "set a", "unset a b", "sleep soon", "task --bogus"] {
+        assert!(
+            matches!(shell.feed_line(line), Err(HackshellError::Usage { .. })),
+            "{}",
+            line
+        );
+    }
+
+    shell.feed_line("set a 1").unwrap();
+    shell.feed_line("get a").unwrap();
+    shell.feed_line("sleep 0").unwrap();
+    shell.feed_line("task -h").unwrap();
+}
+
+#[test]
+fn test_builtins_take_dash_words_as_values() {
+    let shell = Hackshell::new("> ").unwrap();
+
+    shell.feed_line("set x -5").unwrap();
+    shell.feed_line("set msg -hello").unwrap();
+    shell.feed_line("set -x 1").unwrap();
+
+    assert_eq!(shell.get_var("x"), Some("-5".to_string()));
+    assert_eq!(shell.get_var("msg"), Some("-hello".to_string()));
+    assert_eq!(shell.get_var("-x"), Some("1".to_string()));
+    shell.feed_line("get -x").unwrap();
+}
@@ -232,3 +232,24 @@ async fn test_async_command_can_spawn_tasks() {
 
     assert!(task_started.load(Ordering::Relaxed));
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_source_runs_async_commands() {
+    let shell = Hackshell::new("> ").unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    shell.add_async_command(AsyncCounter {
+        counter: counter.clone(),
+    });
+
+    let path = std::env::temp_dir().join(format!("hackshell-source-{}.hs", std::process::id()));
+    std::fs::write(&path, "async-count\nasync-count\n").unwrap();
+
+    let result = shell
+        .feed_line_async(&format!("source {}", path.display()))
+        .await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(result.unwrap(), Some("async counted".to_string()));
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
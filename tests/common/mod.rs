@@ -0,0 +1,132 @@
+//! Commands and fixtures shared by the integration tests.
+
+// Each test crate uses only some of them
+#![allow(dead_code)]
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use hackshell::{Command, CommandResult, Hackshell};
+
+/// Writer keeping everything in memory
+#[derive(Clone, Default)]
+pub struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    pub fn take(&self) -> String {
+        String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns its arguments joined by spaces
+pub struct Echo;
+
+impl Command for Echo {
+    fn commands(&self) -> &'static [&'static str] {
+        &["echo"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Returns its arguments"
+    }
+
+    fn run(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        Ok(Some(cmd[1..].join(" ")))
+    }
+}
+
+/// Returns its arguments, one per line, so word boundaries are visible
+pub struct Args;
+
+impl Command for Args {
+    fn commands(&self) -> &'static [&'static str] {
+        &["args"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Returns its arguments"
+    }
+
+    fn run(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        Ok(Some(cmd[1..].join("\n")))
+    }
+}
+
+/// Returns its own label
+pub struct Labeled {
+    names: &'static [&'static str],
+    label: &'static str,
+}
+
+impl Command for Labeled {
+    fn commands(&self) -> &'static [&'static str] {
+        self.names
+    }
+
+    fn help(&self) -> &'static str {
+        "Returns its label"
+    }
+
+    fn run(&self, _s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+        Ok(Some(self.label.to_string()))
+    }
+}
+
+pub fn labeled(names: &'static [&'static str], label: &'static str) -> Labeled {
+    Labeled { names, label }
+}
+
+/// Lines recorded by [`Log`]
+pub type Calls = Arc<Mutex<Vec<String>>>;
+
+/// Records its arguments joined by `sep`, fails when the first one is "fail"
+pub struct Log {
+    calls: Calls,
+    sep: &'static str,
+}
+
+impl Command for Log {
+    fn commands(&self) -> &'static [&'static str] {
+        &["log"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Records a call"
+    }
+
+    fn run(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        let line = cmd[1..].join(self.sep);
+        self.calls.lock().unwrap().push(line.clone());
+
+        if cmd.get(1) == Some(&"fail") {
+            return Err(format!("failed: {}", line).into());
+        }
+
+        Ok(Some(line))
+    }
+}
+
+/// Shell with a [`Log`] joining its arguments with `sep`.
+pub fn log_shell(sep: &'static str) -> (Hackshell, Calls) {
+    let shell = Hackshell::new("> ").unwrap();
+    let calls = Calls::default();
+
+    shell.add_command(Log {
+        calls: calls.clone(),
+        sep,
+    });
+
+    (shell, calls)
+}
@@ -0,0 +1,147 @@
+use hackshell::{Command, CommandResult, Hackshell, TaskOptions};
+
+struct Target;
+
+impl Command for Target {
+    fn commands(&self) -> &'static [&'static str] {
+        &["target", "tgt"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Selects a target"
+    }
+
+    fn complete(&self, _s: &Hackshell, args: &[&str], cursor: usize) -> Vec<String> {
+        match cursor {
+            1 => vec!["alpha".into(), "beta".into(), "bravo".into()],
+            2 if args[1] == "alpha" => vec!["fast".into(), "slow".into()],
+            _ => vec![],
+        }
+    }
+
+    fn run(&self, _s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+        Ok(None)
+    }
+}
+
+#[test]
+fn test_complete_command_names() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Target);
+
+    let (start, candidates) = shell.complete("t");
+    assert_eq!(start, 0);
+    assert_eq!(candidates, vec!["target", "task", "test", "tgt"]);
+
+    let (_, candidates) = shell.complete("");
+    assert!(candidates.contains(&"help".to_string()));
+    assert!(candidates.contains(&"tgt".to_string()));
+}
+
+#[test]
+fn test_complete_command_arguments() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Target);
+
+    let (start, candidates) = shell.complete("target b");
+    assert_eq!(start, 7);
+    assert_eq!(candidates, vec!["beta", "bravo"]);
+
+    let (_, candidates) = shell.complete("tgt  ");
+    assert_eq!(candidates, vec!["alpha", "beta", "bravo"]);
+
+    let (start, candidates) = shell.complete("target alpha s");
+    assert_eq!(start, 13);
+    assert_eq!(candidates, vec!["slow"]);
+
+    let (_, candidates) = shell.complete("target beta ");
+    assert!(candidates.is_empty());
+}
+
+#[test]
+fn test_complete_after_non_ascii_whitespace() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Target);
+
+    // A non-breaking space is two bytes long
+    let (start, candidates) = shell.complete("target\u{a0}b");
+    assert_eq!(start, 8);
+    assert_eq!(candidates, vec!["beta", "bravo"]);
+
+    let (start, candidates) = shell.complete("get\u{3000}x");
+    assert_eq!(start, 6);
+    assert!(candidates.is_empty());
+}
+
+#[test]
+fn test_complete_after_separators() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Target);
+
+    for line in [
+        "echo a | ta",
+        "set x 1; ta",
+        "get x && ta",
+        "get x ||ta",
+        "if ta",
+        "def f { ta",
+    ] {
+        let (start, candidates) = shell.complete(line);
+        assert_eq!(start, line.len() - 2, "{}", line);
+        assert_eq!(candidates, vec!["target", "task"], "{}", line);
+    }
+
+    let (start, candidates) = shell.complete("echo a | target b");
+    assert_eq!(start, 16);
+    assert_eq!(candidates, vec!["beta", "bravo"]);
+
+    // Substitutions are commands too, separators in quotes are not
+    let (start, candidates) = shell.complete("echo \"$(ta");
+    assert_eq!(
+        (start, candidates),
+        (8, vec!["target".into(), "task".into()])
+    );
+    let (start, _) = shell.complete("target 'a|b");
+    assert_eq!(start, 7);
+}
+
+#[test]
+fn test_complete_unknown_command() {
+    let shell = Hackshell::new("> ").unwrap();
+
+    let (_, candidates) = shell.complete("nope a");
+    assert!(candidates.is_empty());
+}
+
+#[test]
+fn test_complete_variable_names() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.set_var("target", "10.0.0.1");
+    shell.set_var("port", "80");
+
+    assert_eq!(shell.complete("get t").1, vec!["target"]);
+    assert_eq!(shell.complete("unset ").1, vec!["port", "target"]);
+    assert!(shell.complete("get port ").1.is_empty());
+}
+
+#[test]
+fn test_complete_task_names() {
+    let shell = Hackshell::new("> ").unwrap();
+
+    shell.spawn("scanner", TaskOptions::default(), |run| {
+        while run.load(std::sync::atomic::Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        None
+    });
+
+    assert_eq!(shell.complete("task -t sc").1, vec!["scanner"]);
+    assert_eq!(shell.complete("task --wait ").1, vec!["scanner"]);
+    assert_eq!(
+        shell.complete("task --h").1,
+        vec!["--help", "--hidden", "--history"]
+    );
+    assert!(shell.complete("task --hidden ").1.is_empty());
+
+    shell.terminate("scanner").unwrap();
+}
@@ -0,0 +1,127 @@
+use hackshell::{CommandContext, Hackshell, error::HackshellError};
+
+mod common;
+
+use common::labeled;
+
+fn shell() -> Hackshell {
+    let shell = Hackshell::new("router> ").unwrap();
+    shell.add_command(labeled(&["show"], "global show"));
+    shell
+        .add_context(
+            CommandContext::new("config")
+                .command(labeled(&["hostname"], "hostname"))
+                .command(labeled(&["show"], "config show")),
+        )
+        .add_context(
+            CommandContext::new("interface")
+                .segment("(if)")
+                .command(labeled(&["shutdown"], "shutdown")),
+        );
+    shell
+}
+
+#[test]
+fn test_enter_and_back() {
+    let shell = shell();
+
+    assert!(matches!(
+        shell.feed_line("hostname"),
+        Err(HackshellError::CommandNotFound)
+    ));
+
+    shell.feed_line("enter config").unwrap();
+    assert_eq!(shell.context_stack(), vec!["config"]);
+    assert_eq!(
+        shell.feed_line("hostname").unwrap().as_deref(),
+        Some("hostname")
+    );
+    assert_eq!(
+        shell.feed_line("show").unwrap().as_deref(),
+        Some("config show")
+    );
+
+    shell.feed_line("back").unwrap();
+    assert!(shell.context_stack().is_empty());
+    assert_eq!(
+        shell.feed_line("show").unwrap().as_deref(),
+        Some("global show")
+    );
+    assert!(shell.feed_line("back").is_err());
+}
+
+#[test]
+fn test_nested_contexts() {
+    let shell = shell();
+    shell.enter("config").unwrap();
+    shell.enter("interface").unwrap();
+
+    assert_eq!(shell.context_stack(), vec!["config", "interface"]);
+    assert_eq!(
+        shell.feed_line("shutdown").unwrap().as_deref(),
+        Some("shutdown")
+    );
+    assert_eq!(
+        shell.feed_line("hostname").unwrap().as_deref(),
+        Some("hostname")
+    );
+    assert!(shell.feed_line("set a 1").is_ok());
+
+    shell.back().unwrap();
+    assert!(shell.feed_line("shutdown").is_err());
+}
+
+#[test]
+fn test_unknown_context() {
+    let shell = shell();
+
+    assert!(matches!(
+        shell.enter("nope"),
+        Err(HackshellError::ContextNotFound)
+    ));
+    assert!(shell.context_stack().is_empty());
+}
+
+#[test]
+fn test_prompt_segments() {
+    let shell = shell();
+    assert_eq!(shell.prompt(), "router> ");
+
+    shell.enter("config").unwrap();
+    assert_eq!(shell.prompt(), "router(config)> ");
+
+    shell.enter("interface").unwrap();
+    assert_eq!(shell.prompt(), "router(config)(if)> ");
+
+    shell.back().unwrap();
+    shell.back().unwrap();
+    assert_eq!(shell.prompt(), "router> ");
+}
+
+#[test]
+fn test_context_completion() {
+    let shell = shell();
+
+    assert_eq!(shell.complete("enter ").1, vec!["config", "interface"]);
+    assert!(shell.complete("host").1.is_empty());
+
+    shell.enter("config").unwrap();
+    assert_eq!(shell.complete("host").1, vec!["hostname"]);
+    assert!(
+        shell
+            .get_commands()
+            .iter()
+            .any(|c| c.commands() == ["hostname"])
+    );
+}
+
+#[test]
+fn test_fork_keeps_contexts_but_not_stack() {
+    let shell = shell();
+    shell.enter("config").unwrap();
+
+    let child = shell.fork("child> ").unwrap();
+    assert!(child.context_stack().is_empty());
+    child.enter("interface").unwrap();
+    assert_eq!(shell.context_stack(), vec!["config"]);
+}
@@ -0,0 +1,275 @@
+use std::{
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
+
+use hackshell::error::HackshellError;
+
+mod common;
+
+use common::log_shell;
+
+fn calls(calls: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+    std::mem::take(&mut *calls.lock().unwrap())
+}
+
+#[test]
+fn test_if() {
+    let (shell, log) = log_shell(" ");
+
+    assert_eq!(
+        shell
+            .feed_line("if log cond; then log yes; else log no; fi")
+            .unwrap(),
+        Some("yes".to_string())
+    );
+    assert_eq!(calls(&log), vec!["cond", "yes"]);
+
+    // A failing condition is not an error
+    assert_eq!(
+        shell
+            .feed_line("if log fail; then log yes; else log no; fi")
+            .unwrap(),
+        Some("no".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("if log fail; then log yes; fi").unwrap(),
+        None
+    );
+    assert_eq!(calls(&log), vec!["fail", "no", "fail"]);
+}
+
+#[test]
+fn test_elif_and_variables() {
+    let (shell, _) = log_shell(" ");
+    let line = r#"if [ "$x" = a ]; then log A; elif test -z "$x"; then log empty; elif ! test $x -gt 10; then log small; else log big; fi"#;
+
+    for (x, expected) in [("a", "A"), ("", "empty"), ("5", "small"), ("42", "big")] {
+        shell.set_var("x", x);
+        assert_eq!(shell.feed_line(line).unwrap(), Some(expected.to_string()));
+    }
+}
+
+#[test]
+fn test_for() {
+    let (shell, log) = log_shell(" ");
+
+    shell.set_var("hosts", "b c");
+    assert_eq!(
+        shell
+            .feed_line("for h in a $hosts 'd e'; do log scan $h; done")
+            .unwrap(),
+        Some("scan d e".to_string())
+    );
+    assert_eq!(calls(&log), vec!["scan a", "scan b c", "scan d e"]);
+    assert_eq!(shell.get_var("h"), Some("d e".to_string()));
+
+    // Without `in`, over the positional parameters
+    shell
+        .feed_line("def each { for x; do log $x; done }; each 1 2")
+        .unwrap();
+    assert_eq!(calls(&log), vec!["1", "2"]);
+
+    assert_eq!(shell.feed_line("for x in; do log $x; done").unwrap(), None);
+    assert!(calls(&log).is_empty());
+}
+
+#[test]
+fn test_loop_errors() {
+    let (shell, log) = log_shell(" ");
+
+    // Like lists, loops go on and return the first error
+    assert_eq!(
+        shell
+            .feed_line("for x in 1 2 3; do log fail $x; done")
+            .unwrap_err()
+            .to_string(),
+        "failed: fail 1"
+    );
+    assert_eq!(calls(&log), vec!["fail 1", "fail 2", "fail 3"]);
+
+    assert!(matches!(
+        shell.feed_line("for x in 1 2; do log $x; exit; done"),
+        Err(HackshellError::Exit)
+    ));
+    assert_eq!(calls(&log), vec!["1"]);
+}
+
+#[test]
+fn test_while_break_continue() {
+    let (shell, log) = log_shell(" ");
+
+    shell.set_var("i", "0");
+    shell
+        .feed_line(
+            "while test $i != 011111; do
+                set i $(log $i)1
+                if test $i = 01; then continue; fi
+                log body $i
+                if test $i = 0111; then break; fi
+            done",
+        )
+        .unwrap();
+
+    assert_eq!(calls(&log), vec!["0", "01", "body 011", "011", "body 0111"]);
+
+    // The condition can break the loop too
+    assert_eq!(
+        shell
+            .feed_line("while log fail; do log never; done")
+            .unwrap(),
+        None
+    );
+    assert_eq!(calls(&log), vec!["fail"]);
+}
+
+#[test]
+fn test_nested_loops() {
+    let (shell, log) = log_shell(" ");
+
+    shell
+        .feed_line(
+            "for a in 1 2 3; do
+                for b in x y z; do
+                    if test $b = y; then continue 2; fi
+                    if test $a = 3; then break 2; fi
+                    log $a$b
+                done
+            done",
+        )
+        .unwrap();
+
+    assert_eq!(calls(&log), vec!["1x", "2x"]);
+
+    // More levels than loops leaves them all
+    shell
+        .feed_line("for a in 1 2; do for b in 1 2; do log $a$b; break 5; done; done; log after")
+        .unwrap();
+    assert_eq!(calls(&log), vec!["11", "after"]);
+}
+
+#[test]
+fn test_break_outside_loops() {
+    let (shell, log) = log_shell(" ");
+
+    assert!(shell.feed_line("break").is_err());
+    assert!(shell.feed_line("continue").is_err());
+
+    // Neither in a function nor a substitution called from a loop
+    shell.feed_line("def f { break }").unwrap();
+    assert!(shell.feed_line("for x in 1 2; do f; log $x; done").is_err());
+    assert_eq!(calls(&log), vec!["1", "2"]);
+
+    assert!(shell.feed_line("for x in 1; do break 0; done").is_err());
+}
+
+#[test]
+fn test_negation() {
+    let (shell, _) = log_shell(" ");
+
+    assert_eq!(shell.feed_line("! log fail").unwrap(), None);
+    assert!(shell.feed_line("! log ok").is_err());
+    assert_eq!(
+        shell.feed_line("! log fail && log then").unwrap(),
+        Some("then".to_string())
+    );
+}
+
+#[test]
+fn test_test_builtin() {
+    let (shell, _) = log_shell(" ");
+
+    for line in [
+        "test a",
+        "test -n a",
+        "test -z ''",
+        "test a = a",
+        "test a != b",
+        "test 2 -lt 10",
+        "test ! 2 -ge 10",
+        "[ -1 -le 0 ]",
+    ] {
+        assert!(shell.feed_line(line).is_ok(), "{}", line);
+    }
+
+    for line in [
+        "test",
+        "test ''",
+        "test a = b",
+        "test 10 -lt 2",
+        "test x -eq 1",
+        "[ a = a",
+        "test a b c d",
+    ] {
+        assert!(shell.feed_line(line).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn test_control_syntax_errors() {
+    let (shell, log) = log_shell(" ");
+
+    for line in [
+        "if log a; then log b",
+        "if log a; log b; fi",
+        "if; then log a; fi",
+        "if log a; then; fi",
+        "for x in a b do log $x; done",
+        "for 'x' in a; do log $x; done",
+        "for x in a; do log $x",
+        "while log a; do; done",
+        "fi",
+        "log a; done",
+        "log a && then",
+    ] {
+        assert!(
+            matches!(shell.feed_line(line), Err(HackshellError::Syntax(_))),
+            "{}",
+            line
+        );
+    }
+
+    assert!(calls(&log).is_empty());
+
+    // Keywords are only special at the start of a command
+    assert_eq!(
+        shell.feed_line("log if then fi").unwrap(),
+        Some("if then fi".to_string())
+    );
+}
+
+#[test]
+fn test_multiline_script() {
+    let (shell, log) = log_shell(" ");
+
+    let script = "for port in 22 80 443
+do
+    if test $port = 80
+    then
+        log web $port
+    else
+        log other $port
+    fi
+done
+";
+
+    shell.run_reader(Cursor::new(script)).unwrap();
+    assert_eq!(calls(&log), vec!["other 22", "web 80", "other 443"]);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_control_async() {
+    let (shell, log) = log_shell(" ");
+
+    let result = tokio::spawn(async move {
+        shell
+            .feed_line_async("for x in a b; do if test $x = b; then break; fi; log $x; done")
+            .await
+    })
+    .await
+    .unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(calls(&log), vec!["a"]);
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "derive")]
+
+use hackshell::{Command, CommandResult, Hackshell};
+
+/// Prints the current target
+#[derive(Command)]
+#[command(name = "target", aliases = ["tgt"], category = "Recon")]
+struct Target;
+
+impl Target {
+    fn execute(&self, s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+        Ok(s.get_var("target"))
+    }
+}
+
+/// Counts its arguments
+///
+/// Second paragraph.
+#[derive(Command)]
+struct Count {
+    offset: usize,
+}
+
+impl Count {
+    fn execute(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        Ok(Some((cmd.len() - 1 + self.offset).to_string()))
+    }
+}
+
+/// Greets someone
+#[hackshell::command(aliases = ["hi"], category = "Fun")]
+fn greet(_s: &Hackshell, cmd: &[&str]) -> CommandResult {
+    Ok(Some(format!("Hello {}", cmd.get(1).unwrap_or(&"world"))))
+}
+
+#[hackshell::command(name = "add", help = "Adds two numbers")]
+pub fn add_numbers(_s: &Hackshell, cmd: &[&str]) -> CommandResult {
+    let a: i64 = cmd.get(1).ok_or("Missing a")?.parse()?;
+    let b: i64 = cmd.get(2).ok_or("Missing b")?.parse()?;
+    Ok(Some((a + b).to_string()))
+}
+
+#[test]
+fn test_derive_metadata() {
+    assert_eq!(Target.commands(), &["target", "tgt"]);
+    assert_eq!(Target.help(), "Prints the current target");
+    assert_eq!(Target.category(), "Recon");
+
+    let count = Count { offset: 0 };
+    assert_eq!(count.commands(), &["count"]);
+    assert_eq!(count.help(), "Counts its arguments Second paragraph.");
+    assert_eq!(count.category(), "User");
+}
+
+#[test]
+fn test_derive_runs_execute() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Target).add_command(Count { offset: 10 });
+    shell.set_var("target", "10.0.0.1");
+
+    assert_eq!(
+        shell.feed_line("tgt").unwrap(),
+        Some("10.0.0.1".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("count a b").unwrap(),
+        Some("12".to_string())
+    );
+}
+
+#[test]
+fn test_command_attribute_on_function() {
+    assert_eq!(greet.commands(), &["greet", "hi"]);
+    assert_eq!(greet.help(), "Greets someone");
+    assert_eq!(greet.category(), "Fun");
+    assert_eq!(add_numbers.commands(), &["add"]);
+    assert_eq!(add_numbers.help(), "Adds two numbers");
+
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(greet).add_command(add_numbers);
+
+    assert_eq!(
+        shell.feed_line("hi bob").unwrap(),
+        Some("Hello bob".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("greet").unwrap(),
+        Some("Hello world".to_string())
+    );
+    assert_eq!(shell.feed_line("add 2 40").unwrap(), Some("42".to_string()));
+    assert!(shell.feed_line("add 2").is_err());
+}
+
+#[cfg(feature = "async")]
+mod async_tests {
+    use super::*;
+    use hackshell::AsyncCommand;
+
+    /// Waits a bit, then answers
+    #[derive(Command)]
+    #[command(name = "slow", asynchronous)]
+    struct Slow;
+
+    impl Slow {
+        async fn execute(&self, _s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            Ok(Some("done".to_string()))
+        }
+    }
+
+    /// Echoes asynchronously
+    #[hackshell::command(name = "aecho")]
+    async fn async_echo(_s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        tokio::task::yield_now().await;
+        Ok(Some(cmd[1..].join(" ")))
+    }
+
+    #[tokio::test]
+    async fn test_async_derive_and_attribute() {
+        assert_eq!(AsyncCommand::help(&Slow), "Waits a bit, then answers");
+        assert_eq!(AsyncCommand::commands(&async_echo), &["aecho"]);
+
+        let shell = Hackshell::new("> ").unwrap();
+        shell.add_async_command(Slow).add_async_command(async_echo);
+
+        assert_eq!(
+            shell.feed_line_async("slow").await.unwrap(),
+            Some("done".to_string())
+        );
+        assert_eq!(
+            shell.feed_line_async("aecho a b").await.unwrap(),
+            Some("a b".to_string())
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use hackshell::{Command, CommandResult, Hackshell, error::HackshellError};
+
+mod common;
+
+use common::Args;
+
+fn shell() -> Hackshell {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Args);
+    shell.set_var("target", "10.0.0.1");
+    shell.set_var("port", "22");
+    shell.set_var("spaced", "a b");
+    shell
+}
+
+fn args(shell: &Hackshell, line: &str) -> Option<String> {
+    shell.feed_line(line).unwrap()
+}
+
+#[test]
+fn test_expand_variables() {
+    let shell = shell();
+
+    assert_eq!(args(&shell, "args $target"), Some("10.0.0.1".to_string()));
+    assert_eq!(
+        args(&shell, "args ${target}:$port"),
+        Some("10.0.0.1:22".to_string())
+    );
+    assert_eq!(
+        args(&shell, "args \"host=$target\""),
+        Some("host=10.0.0.1".to_string())
+    );
+    assert_eq!(args(&shell, "args $TARGET"), Some("10.0.0.1".to_string()));
+}
+
+#[test]
+fn test_expansion_is_not_split() {
+    let shell = shell();
+
+    assert_eq!(args(&shell, "args $spaced x"), Some("a b\nx".to_string()));
+}
+
+#[test]
+fn test_expand_defaults() {
+    let shell = shell();
+    shell.set_var("empty", "");
+
+    assert_eq!(
+        args(&shell, "args ${missing:-fallback} ${empty:-e} ${port:-80}"),
+        Some("fallback\ne\n22".to_string())
+    );
+    assert_eq!(
+        args(&shell, "args \"${missing:-two words}\""),
+        Some("two words".to_string())
+    );
+
+    // Defaults are expanded
+    assert_eq!(
+        args(
+            &shell,
+            "args ${missing:-$target:${port}} ${missing:-${empty:-'$x'}}"
+        ),
+        Some("10.0.0.1:22\n$x".to_string())
+    );
+    assert_eq!(
+        args(&shell, "args ${missing:-$(args a)} \"${missing:-}\" x"),
+        Some("a\n\nx".to_string())
+    );
+}
+
+#[test]
+fn test_missing_variables() {
+    let shell = shell();
+
+    // Unquoted empty expansions are dropped, quoted ones are kept
+    assert_eq!(args(&shell, "args $missing x"), Some("x".to_string()));
+    assert_eq!(args(&shell, "args \"$missing\" x"), Some("\nx".to_string()));
+    assert_eq!(args(&shell, "args pre$missing"), Some("pre".to_string()));
+}
+
+#[test]
+fn test_no_expansion_in_single_quotes_or_escaped() {
+    let shell = shell();
+
+    assert_eq!(args(&shell, "args '$target'"), Some("$target".to_string()));
+    assert_eq!(args(&shell, "args \\$target"), Some("$target".to_string()));
+    assert_eq!(
+        args(&shell, "args \"\\$target\""),
+        Some("$target".to_string())
+    );
+    assert_eq!(args(&shell, "args $ a$"), Some("$\na$".to_string()));
+}
+
+#[test]
+fn test_expansion_with_builtins() {
+    let shell = shell();
+
+    shell
+        .feed_line("set url http://$target:${port}/; set copy $url")
+        .unwrap();
+
+    assert_eq!(
+        shell.get_var("copy"),
+        Some("http://10.0.0.1:22/".to_string())
+    );
+}
+
+#[test]
+fn test_expansion_happens_at_execution() {
+    let shell = shell();
+
+    assert_eq!(
+        args(&shell, "set target other; args $target"),
+        Some("other".to_string())
+    );
+}
+
+#[test]
+fn test_bad_substitution() {
+    let shell = shell();
+
+    for line in [
+        "args ${}",
+        "args ${target",
+        "args ${target:+x}",
+        "args ${a-b}",
+    ] {
+        assert!(
+            matches!(shell.feed_line(line), Err(HackshellError::Syntax(_))),
+            "{}",
+            line
+        );
+    }
+}
+
+/// Returns the address of a few known hosts
+struct Resolve;
+
+impl Command for Resolve {
+    fn commands(&self) -> &'static [&'static str] {
+        &["resolve"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Resolves a host"
+    }
+
+    fn run(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        match cmd.get(1) {
+            Some(&"router") => Ok(Some("192.168.1.1\n".to_string())),
+            Some(&"nothing") => Ok(None),
+            _ => Err("Unknown host".into()),
+        }
+    }
+}
+
+#[test]
+fn test_command_substitution() {
+    let shell = shell();
+    shell.add_command(Resolve);
+
+    shell.feed_line("set ip $(resolve router)").unwrap();
+    assert_eq!(shell.get_var("ip"), Some("192.168.1.1".to_string()));
+
+    assert_eq!(
+        args(&shell, "args ip=$(resolve router) \"[$(args a b)]\""),
+        Some("ip=192.168.1.1\n[a\nb]".to_string())
+    );
+}
+
+#[test]
+fn test_nested_command_substitution() {
+    let shell = shell();
+    shell.add_command(Resolve);
+    shell.set_var("host", "router");
+
+    assert_eq!(
+        args(&shell, "args $(args $(resolve $host) 'x)' \")\")"),
+        Some("192.168.1.1\nx)\n)".to_string())
+    );
+    assert_eq!(
+        args(&shell, "args $(set a 1; args $a && args ok)"),
+        Some("ok".to_string())
+    );
+}
+
+#[test]
+fn test_empty_command_substitution() {
+    let shell = shell();
+    shell.add_command(Resolve);
+
+    assert_eq!(
+        args(&shell, "args $(resolve nothing) \"$(resolve nothing)\" x"),
+        Some("\nx".to_string())
+    );
+}
+
+#[test]
+fn test_failing_command_substitution() {
+    let shell = shell();
+    shell.add_command(Resolve);
+
+    assert!(shell.feed_line("set ip $(resolve nowhere)").is_err());
+    assert_eq!(shell.get_var("ip"), None);
+
+    // Exit fails the substitution instead of leaving the shell
+    let err = shell.feed_line("set ip $(exit)").unwrap_err();
+    assert!(!matches!(err, HackshellError::Exit));
+    assert_eq!(shell.get_var("ip"), None);
+    assert_eq!(
+        shell.feed_line("args $(exit) || args after").unwrap(),
+        Some("after".to_string())
+    );
+
+    assert!(matches!(
+        shell.feed_line("args $(resolve router"),
+        Err(HackshellError::Syntax(_))
+    ));
+    assert!(matches!(
+        shell.feed_line("args '$(resolve nowhere)'"),
+        Ok(Some(s)) if s == "$(resolve nowhere)"
+    ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_command_substitution_async() {
+    let shell = shell();
+    shell.add_command(Resolve);
+
+    assert_eq!(
+        shell
+            .feed_line_async("args $(resolve router)")
+            .await
+            .unwrap(),
+        Some("192.168.1.1".to_string())
+    );
+}
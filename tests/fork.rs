@@ -1,4 +1,7 @@
-use hackshell::{Hackshell, TaskOptions};
+use hackshell::{
+    CollisionPolicy, Command, CommandResult, ForkCommands, ForkOptions, Hackshell, OnError,
+    TaskOptions, error::HackshellError,
+};
 
 #[test]
 fn test_fork_inherits_environment() {
@@ -155,3 +158,161 @@ fn test_fork_preserves_case_insensitive_vars() {
     assert_eq!(child.get_var("myvar"), Some("value".to_string()));
     assert_eq!(child.get_var("MYVAR"), Some("value".to_string()));
 }
+
+#[test]
+fn test_parent_root_and_children() {
+    let root = Hackshell::new("root> ").unwrap();
+    assert!(root.parent().is_none());
+
+    let child = root.fork("child> ").unwrap();
+    let grandchild = child.fork("grandchild> ").unwrap();
+
+    assert_eq!(grandchild.parent().unwrap().prompt(), "child> ");
+    assert_eq!(grandchild.root().prompt(), "root> ");
+    assert_eq!(root.root().prompt(), "root> ");
+
+    assert_eq!(root.children().len(), 1);
+    assert_eq!(child.children()[0].prompt(), "grandchild> ");
+
+    drop(grandchild);
+    assert!(child.children().is_empty());
+}
+
+#[test]
+fn test_fork_inherit_env() {
+    let parent = Hackshell::new("parent> ").unwrap();
+    parent.set_var("target", "10.0.0.1");
+
+    let opts = ForkOptions {
+        inherit_env: true,
+        ..Default::default()
+    };
+    let child = parent.fork_with("child> ", opts).unwrap();
+
+    // Parent changes after the fork are visible
+    parent.set_var("port", "22");
+    assert_eq!(child.get_var("port"), Some("22".to_string()));
+    assert_eq!(child.feed_line("get target").unwrap(), None);
+
+    // Local variables shadow the parent's
+    child.set_var("target", "10.0.0.2");
+    assert_eq!(child.get_var("target"), Some("10.0.0.2".to_string()));
+    assert_eq!(parent.get_var("target"), Some("10.0.0.1".to_string()));
+    assert_eq!(child.env().len(), 2);
+}
+
+#[test]
+fn test_export_to_parent() {
+    let parent = Hackshell::new("parent> ").unwrap();
+    let child = parent.fork("child> ").unwrap();
+
+    child.set_var("found", "yes");
+    child.export("found").unwrap();
+    assert_eq!(parent.get_var("found"), Some("yes".to_string()));
+
+    child.feed_line("export creds admin:admin").unwrap();
+    assert_eq!(parent.get_var("creds"), Some("admin:admin".to_string()));
+
+    assert!(child.export("missing").is_err());
+    assert!(parent.export("found").is_err());
+}
+
+struct Ping;
+
+impl Command for Ping {
+    fn commands(&self) -> &'static [&'static str] {
+        &["ping"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Answers pong"
+    }
+
+    fn run(&self, _s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+        Ok(Some("pong".to_string()))
+    }
+}
+
+#[test]
+fn test_fork_shared_commands() {
+    let parent = Hackshell::new("parent> ").unwrap();
+    parent.add_command(Ping);
+
+    let opts = ForkOptions {
+        commands: ForkCommands::Shared,
+        ..Default::default()
+    };
+    let child = parent.fork_with("child> ", opts).unwrap();
+    assert_eq!(child.feed_line("ping").unwrap().as_deref(), Some("pong"));
+
+    child.remove_command("ping");
+    assert!(matches!(
+        parent.feed_line("ping"),
+        Err(HackshellError::CommandNotFound)
+    ));
+    assert!(parent.feed_line("help").is_ok());
+}
+
+#[test]
+fn test_fork_copied_commands() {
+    let parent = Hackshell::new("parent> ").unwrap();
+    parent.add_command(Ping);
+
+    assert!(parent.fork("child> ").unwrap().feed_line("ping").is_err());
+
+    let opts = ForkOptions {
+        commands: ForkCommands::Copied,
+        ..Default::default()
+    };
+    let child = parent.fork_with("child> ", opts).unwrap();
+    assert_eq!(child.feed_line("ping").unwrap().as_deref(), Some("pong"));
+
+    // The copies are independent
+    child.remove_command("ping");
+    assert_eq!(parent.feed_line("ping").unwrap().as_deref(), Some("pong"));
+    parent.remove_command("help");
+    assert!(child.feed_line("help").is_ok());
+}
+
+#[test]
+fn test_fork_shared_tasks() {
+    let parent = Hackshell::new("parent> ").unwrap();
+    let opts = ForkOptions {
+        share_tasks: true,
+        ..Default::default()
+    };
+    let child = parent.fork_with("child> ", opts).unwrap();
+
+    child.spawn("scan", TaskOptions::default(), |run| {
+        while run.load(std::sync::atomic::Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        None
+    });
+
+    assert_eq!(parent.get_tasks()[0].name, "scan");
+    parent.terminate("scan").unwrap();
+    assert!(child.get_tasks().is_empty());
+}
+
+#[test]
+fn test_fork_copies_options() {
+    let parent = Hackshell::new("parent> ").unwrap();
+    parent.set_collision_policy(CollisionPolicy::Error);
+    parent.set_script_on_error(OnError::Continue);
+    parent.set_glob_expansion(true);
+
+    let child = parent.fork("child> ").unwrap();
+
+    assert!(child.glob_expansion());
+    child.add_command(Ping);
+    assert!(matches!(
+        child.try_add_command(Ping),
+        Err(HackshellError::CommandExists(_))
+    ));
+
+    // The failing line doesn't stop the script
+    child.set_stderr(std::io::sink());
+    assert!(child.run_reader("nope\nset a 1".as_bytes()).is_err());
+    assert_eq!(child.get_var("a"), Some("1".to_string()));
+}
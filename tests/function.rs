@@ -0,0 +1,201 @@
+use std::io::Cursor;
+
+use hackshell::error::HackshellError;
+
+mod common;
+
+use common::{Buffer, log_shell};
+
+#[test]
+fn test_define_and_call() {
+    let (shell, calls) = log_shell("|");
+
+    assert_eq!(
+        shell
+            .feed_line("def recon { set x $1; log $x; log done }")
+            .unwrap(),
+        None
+    );
+    assert_eq!(shell.functions(), vec!["recon"]);
+
+    // Nothing ran yet
+    assert!(calls.lock().unwrap().is_empty());
+
+    assert_eq!(
+        shell.feed_line("recon 10.0.0.1").unwrap(),
+        Some("done".to_string())
+    );
+    assert_eq!(shell.get_var("x"), Some("10.0.0.1".to_string()));
+    assert_eq!(*calls.lock().unwrap(), vec!["10.0.0.1", "done"]);
+}
+
+#[test]
+fn test_positional_parameters() {
+    let (shell, _) = log_shell("|");
+
+    shell
+        .feed_line(r#"def f { log "$0" $# $2 "$@" "[$@]" $3x $12; }"#)
+        .unwrap();
+
+    assert_eq!(
+        shell.feed_line("f a 'b c'").unwrap(),
+        Some("f|2|b c|a|b c|[a b c]|x|a2".to_string())
+    );
+
+    // `$@` gives one word per argument, none without arguments
+    shell.feed_line("def g { log $@ }").unwrap();
+    assert_eq!(shell.feed_line("g").unwrap(), Some(String::new()));
+
+    // Outside functions
+    assert_eq!(
+        shell.feed_line("log $1 $# ${@:-none}").unwrap(),
+        Some("0|none".to_string())
+    );
+}
+
+#[test]
+fn test_function_errors_and_pipelines() {
+    let (shell, calls) = log_shell("|");
+
+    shell
+        .feed_line("def f { log fail $1; log after }; def g { log $1 }")
+        .unwrap();
+
+    assert_eq!(
+        shell.feed_line("f x").unwrap_err().to_string(),
+        "failed: fail|x"
+    );
+    assert_eq!(
+        shell.feed_line("f y || log handled").unwrap(),
+        Some("handled".to_string())
+    );
+    assert_eq!(shell.feed_line("g a | log").unwrap(), Some(String::new()));
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec!["fail|x", "after", "fail|y", "after", "handled", "a", ""]
+    );
+}
+
+#[test]
+fn test_functions_call_functions() {
+    let (shell, _) = log_shell("|");
+
+    shell
+        .feed_line("def inner { log in $1 }; def outer { inner $2 }")
+        .unwrap();
+    assert_eq!(
+        shell.feed_line("outer a b").unwrap(),
+        Some("in|b".to_string())
+    );
+
+    // Redefining replaces the function, even builtins can be shadowed
+    shell.feed_line("def inner { log new }").unwrap();
+    assert_eq!(shell.feed_line("outer").unwrap(), Some("new".to_string()));
+
+    shell.feed_line("def env { log shadowed }").unwrap();
+    assert_eq!(
+        shell.feed_line("env").unwrap(),
+        Some("shadowed".to_string())
+    );
+
+    assert!(shell.remove_function("env"));
+    assert!(!shell.remove_function("env"));
+    assert_eq!(shell.feed_line("env").unwrap(), None);
+}
+
+#[test]
+fn test_infinite_recursion() {
+    let (shell, _) = log_shell("|");
+
+    shell.feed_line("def f { f }").unwrap();
+
+    assert!(
+        shell
+            .feed_line("f")
+            .unwrap_err()
+            .to_string()
+            .contains("maximum call depth")
+    );
+}
+
+#[test]
+fn test_def_syntax_errors() {
+    let (shell, _) = log_shell("|");
+
+    for line in [
+        "def",
+        "def f",
+        "def f log a }",
+        "def f { log a",
+        "def 'f' { log a }",
+        "def f { log a } log b",
+        "def f { log a; ",
+    ] {
+        assert!(
+            matches!(shell.feed_line(line), Err(HackshellError::Syntax(_))),
+            "{}",
+            line
+        );
+    }
+
+    assert!(shell.functions().is_empty());
+
+    // Outside of a function, `}` is a regular word
+    assert_eq!(shell.feed_line("log }").unwrap(), Some("}".to_string()));
+}
+
+#[test]
+fn test_multiline_definition_in_script() {
+    let (shell, calls) = log_shell("|");
+
+    let script = "def scan {
+    # comment inside the body
+    log scanning $1
+
+    log done
+}
+scan 10.0.0.1
+def broken {
+log never
+";
+
+    let err = shell.run_reader(Cursor::new(script)).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "line 8: Syntax error: Unexpected end of line"
+    );
+    assert_eq!(*calls.lock().unwrap(), vec!["scanning|10.0.0.1", "done"]);
+    assert_eq!(shell.functions(), vec!["scan"]);
+}
+
+#[test]
+fn test_functions_in_help_and_fork() {
+    let (shell, _) = log_shell("|");
+    let stdout = Buffer::default();
+
+    shell.set_stdout(stdout.clone());
+    shell.feed_line("def recon { log a }").unwrap();
+    shell.feed_line("help").unwrap();
+
+    let help = stdout.take();
+    let functions = help.split("[Functions]").nth(1).unwrap();
+    assert!(functions.contains("recon"));
+
+    assert_eq!(shell.complete("rec").1, vec!["recon"]);
+
+    let child = shell.fork("child> ").unwrap();
+    assert_eq!(child.functions(), vec!["recon"]);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_functions_async() {
+    let (shell, calls) = log_shell("|");
+
+    shell
+        .feed_line_async("def f { log $1 && log $2 }; f a b")
+        .await
+        .unwrap();
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b"]);
+}
@@ -0,0 +1,149 @@
+use std::{fs, path::PathBuf};
+
+use hackshell::Hackshell;
+
+mod common;
+
+use common::Args;
+
+/// Directory with a few files, removed on drop
+struct Tree(PathBuf);
+
+impl Tree {
+    fn new(name: &str) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("hackshell-glob-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+
+        for file in [
+            "a.txt",
+            "b.txt",
+            "c.log",
+            ".hidden.txt",
+            "sub/d.json",
+            "sub/deep/e.json",
+            "x*.txt",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        Self(root)
+    }
+
+    fn paths(&self, files: &[&str]) -> Option<String> {
+        Some(
+            files
+                .iter()
+                .map(|f| self.0.join(f).display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn shell(tree: &Tree) -> Hackshell {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Args);
+    shell.set_var("dir", tree.0.to_str().unwrap());
+    shell.set_glob_expansion(true);
+    shell
+}
+
+#[test]
+fn test_glob_patterns() {
+    let tree = Tree::new("patterns");
+    let shell = shell(&tree);
+
+    assert_eq!(
+        shell.feed_line("args $dir/*.txt").unwrap(),
+        tree.paths(&["a.txt", "b.txt", "x*.txt"])
+    );
+    assert_eq!(
+        shell.feed_line("args $dir/?.*").unwrap(),
+        tree.paths(&["a.txt", "b.txt", "c.log"])
+    );
+    assert_eq!(
+        shell.feed_line("args $dir/[ac].*").unwrap(),
+        tree.paths(&["a.txt", "c.log"])
+    );
+    assert_eq!(
+        shell.feed_line("args $dir/**/*.json").unwrap(),
+        tree.paths(&["sub/d.json", "sub/deep/e.json"])
+    );
+
+    // Hidden files are never matched
+    assert_eq!(
+        shell.feed_line("args $dir/.h*").unwrap(),
+        tree.paths(&[".h*"])
+    );
+}
+
+#[test]
+fn test_glob_quoting() {
+    let tree = Tree::new("quoting");
+    let shell = shell(&tree);
+
+    assert_eq!(
+        shell.feed_line("args \"$dir/*.txt\"").unwrap(),
+        tree.paths(&["*.txt"])
+    );
+    assert_eq!(
+        shell.feed_line("args $dir/x'*'.txt").unwrap(),
+        tree.paths(&["x*.txt"])
+    );
+    assert_eq!(
+        shell.feed_line("args $dir/\\*.txt").unwrap(),
+        tree.paths(&["*.txt"])
+    );
+
+    // Expanded values are never patterns
+    shell.set_var("pattern", "*.txt");
+    assert_eq!(
+        shell.feed_line("args $dir/$pattern").unwrap(),
+        tree.paths(&["*.txt"])
+    );
+}
+
+#[test]
+fn test_glob_without_matches() {
+    let tree = Tree::new("nomatch");
+    let shell = shell(&tree);
+
+    assert_eq!(
+        shell.feed_line("args $dir/*.nope [ x").unwrap(),
+        Some(format!("{}/*.nope\n[\nx", tree.0.display()))
+    );
+    assert!(shell.feed_line("[ a = a ]").is_ok());
+}
+
+#[test]
+fn test_glob_toggle() {
+    let tree = Tree::new("toggle");
+    let shell = shell(&tree);
+
+    let child = shell.fork("child> ").unwrap();
+    assert!(child.glob_expansion());
+
+    shell.set_glob_expansion(false);
+    assert_eq!(
+        shell.feed_line("args $dir/*.log").unwrap(),
+        tree.paths(&["*.log"])
+    );
+
+    shell.set_glob_expansion(true);
+    assert_eq!(
+        shell
+            .feed_line("for f in $dir/*.log; do set last $f; done")
+            .unwrap(),
+        None
+    );
+    assert_eq!(shell.get_var("last"), tree.paths(&["c.log"]));
+}
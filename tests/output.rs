@@ -0,0 +1,118 @@
+use std::io::Write;
+
+use hackshell::{Command, CommandResult, Hackshell, Output};
+
+mod common;
+
+use common::Buffer;
+
+/// Prints a warning before failing
+struct Warn;
+
+impl Command for Warn {
+    fn commands(&self) -> &'static [&'static str] {
+        &["warn"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Warns and fails"
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        writeln!(s.stderr(), "warning: {}", cmd[1..].join(" "))?;
+        Err("failed".into())
+    }
+}
+
+fn shell() -> (Hackshell, Buffer, Buffer) {
+    let shell = Hackshell::new("> ").unwrap();
+    let (stdout, stderr) = (Buffer::default(), Buffer::default());
+
+    shell.set_stdout(stdout.clone());
+    shell.set_stderr(stderr.clone());
+    shell.add_command(Warn);
+
+    (shell, stdout, stderr)
+}
+
+#[test]
+fn test_builtins_use_shell_output() {
+    let (shell, stdout, stderr) = shell();
+
+    shell.feed_line("set a 1; get a; env").unwrap();
+    assert_eq!(stdout.take(), "1\na=1\n");
+
+    shell.feed_line("help").unwrap();
+    assert!(stdout.take().contains("Lists and manages tasks"));
+
+    shell.feed_line("task").unwrap();
+    assert_eq!(stdout.take(), "");
+    assert_eq!(stderr.take(), "No running tasks\n");
+}
+
+#[test]
+fn test_stderr() {
+    let (shell, stdout, stderr) = shell();
+
+    assert!(shell.feed_line("warn low disk").is_err());
+    assert_eq!(stdout.take(), "");
+    assert_eq!(stderr.take(), "warning: low disk\n");
+
+    // Errors are not captured by pipes or substitutions
+    assert!(shell.feed_line("set x $(warn a)").is_err());
+    assert_eq!(stderr.take(), "warning: a\n");
+}
+
+#[test]
+fn test_per_invocation_output() {
+    let (shell, stdout, stderr) = shell();
+    let (out, err) = (Buffer::default(), Buffer::default());
+
+    shell.set_var("a", "1");
+
+    let invocation = shell
+        .with_stdout(Output::new(out.clone()))
+        .with_stderr(Output::new(err.clone()));
+
+    invocation.feed_line("get a").unwrap();
+    assert!(invocation.feed_line("warn x").is_err());
+    assert_eq!(out.take(), "1\n");
+    assert_eq!(err.take(), "warning: x\n");
+
+    // The shell itself is unchanged
+    shell.feed_line("get a").unwrap();
+    assert_eq!(stdout.take(), "1\n");
+    assert_eq!(stderr.take(), "");
+}
+
+#[test]
+fn test_fork_inherits_output() {
+    let (shell, stdout, _) = shell();
+
+    let child = shell.fork("child> ").unwrap();
+    child.feed_line("set b 2; get b").unwrap();
+
+    assert_eq!(stdout.take(), "2\n");
+}
+
+#[test]
+fn test_printer_without_terminal() {
+    let shell = Hackshell::new("> ").unwrap();
+    let buf = Buffer::default();
+    shell.set_stdout(buf.clone());
+
+    let printer = shell.printer();
+    printer.print("scan done").unwrap();
+    assert_eq!(buf.take(), "scan done\n");
+
+    // Lines are printed whole
+    let mut printer = shell.printer();
+    write!(printer, "host up").unwrap();
+    assert_eq!(buf.take(), "");
+    writeln!(printer, ": 10.0.0.1").unwrap();
+    assert_eq!(buf.take(), "host up: 10.0.0.1\n");
+
+    write!(printer, "partial").unwrap();
+    printer.flush().unwrap();
+    assert_eq!(buf.take(), "partial\n");
+}
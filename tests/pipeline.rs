@@ -0,0 +1,156 @@
+use hackshell::{Command, CommandResult, Hackshell, error::HackshellError};
+
+mod common;
+
+use common::Echo;
+
+/// Returns the input lines containing the pattern
+struct Grep;
+
+impl Command for Grep {
+    fn commands(&self) -> &'static [&'static str] {
+        &["grep"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Filters the piped input"
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        let pattern = cmd.get(1).ok_or("Missing pattern")?;
+        let input = s.input().ok_or("Nothing piped")?;
+
+        Ok(Some(
+            input
+                .lines()
+                .filter(|l| l.contains(pattern))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ))
+    }
+}
+
+/// Returns the piped input in uppercase
+struct Upper;
+
+impl Command for Upper {
+    fn commands(&self) -> &'static [&'static str] {
+        &["upper"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Uppercases the piped input"
+    }
+
+    fn run(&self, s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+        Ok(s.input().map(|i| i.to_uppercase()))
+    }
+}
+
+fn shell() -> Hackshell {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Echo).add_command(Grep).add_command(Upper);
+    shell
+}
+
+#[test]
+fn test_pipe_output_into_input() {
+    let shell = shell();
+
+    assert_eq!(
+        shell.feed_line("echo hello | upper").unwrap(),
+        Some("HELLO".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("echo hello|upper").unwrap(),
+        Some("HELLO".to_string())
+    );
+}
+
+#[test]
+fn test_pipe_chain() {
+    let shell = shell();
+
+    assert_eq!(
+        shell
+            .feed_line("echo \"foo 1\nbar 2\nfoo 3\" | grep foo | grep 3 | upper")
+            .unwrap(),
+        Some("FOO 3".to_string())
+    );
+}
+
+#[test]
+fn test_no_input_without_pipe() {
+    let shell = shell();
+
+    assert_eq!(shell.feed_line("upper").unwrap(), None);
+    assert!(shell.feed_line("grep foo").is_err());
+
+    // The previous command returned nothing
+    assert_eq!(shell.feed_line("set a b | upper").unwrap(), None);
+}
+
+#[test]
+fn test_quoted_pipe_is_literal() {
+    let shell = shell();
+
+    assert_eq!(
+        shell.feed_line("echo 'a | b' \"c|d\" e\\|f").unwrap(),
+        Some("a | b c|d e|f".to_string())
+    );
+}
+
+#[test]
+fn test_pipe_syntax_errors() {
+    let shell = shell();
+
+    for line in ["| upper", "echo a |", "echo a | | upper"] {
+        assert!(
+            matches!(shell.feed_line(line), Err(HackshellError::Syntax(_))),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_pipe_stops_on_error() {
+    let shell = shell();
+
+    assert!(matches!(
+        shell.feed_line("nope | upper"),
+        Err(HackshellError::CommandNotFound)
+    ));
+}
+
+#[test]
+fn test_quoting_and_comments() {
+    let shell = shell();
+
+    assert_eq!(
+        shell
+            .feed_line("echo \"a  b\" 'c d' e\\ f # comment")
+            .unwrap(),
+        Some("a  b c d e f".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("echo '' x#y").unwrap(),
+        Some(" x#y".to_string())
+    );
+    assert!(matches!(
+        shell.feed_line("echo 'open"),
+        Err(HackshellError::Syntax(_))
+    ));
+    assert_eq!(shell.feed_line("# only a comment").unwrap(), None);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_pipe_async() {
+    let shell = shell();
+
+    assert_eq!(
+        shell.feed_line_async("echo abc | upper").await.unwrap(),
+        Some("ABC".to_string())
+    );
+}
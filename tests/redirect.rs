@@ -0,0 +1,158 @@
+use std::{fs, io::Write, path::PathBuf};
+
+use hackshell::{Command, CommandResult, Hackshell, error::HackshellError};
+
+/// Prints its first argument and returns the second one
+struct Both;
+
+impl Command for Both {
+    fn commands(&self) -> &'static [&'static str] {
+        &["both"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Prints and returns"
+    }
+
+    fn run(&self, s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        writeln!(s.stdout(), "{}", cmd[1])?;
+        Ok(cmd.get(2).map(|o| o.to_string()))
+    }
+}
+
+/// Returns the piped input
+struct Cat;
+
+impl Command for Cat {
+    fn commands(&self) -> &'static [&'static str] {
+        &["cat"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Returns the piped input"
+    }
+
+    fn run(&self, s: &Hackshell, _cmd: &[&str]) -> CommandResult {
+        Ok(s.input().map(|i| i.to_string()))
+    }
+}
+
+fn shell() -> Hackshell {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Both).add_command(Cat);
+    shell
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "hackshell-redirect-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_redirect_builtin_output() {
+    let shell = shell();
+    let path = temp_file("env");
+
+    shell.set_var("target", "10.0.0.1");
+    shell.set_var("port", "22");
+
+    assert_eq!(
+        shell
+            .feed_line(&format!("env > {}", path.display()))
+            .unwrap(),
+        None
+    );
+
+    let mut lines: Vec<String> = fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    lines.sort();
+
+    assert_eq!(lines, vec!["port=22", "target=10.0.0.1"]);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_redirect_truncates_and_appends() {
+    let shell = shell();
+    let path = temp_file("append");
+    let file = path.display();
+
+    shell.set_var("a", "1");
+
+    shell.feed_line(&format!("get a > {}", file)).unwrap();
+    shell.feed_line(&format!("get a > {}", file)).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "1\n");
+
+    shell
+        .feed_line(&format!("both printed returned >> {}", file))
+        .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "1\nprinted\nreturned\n");
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_redirect_target_expansion() {
+    let shell = shell();
+    let path = temp_file("expanded");
+
+    shell.set_var("out", path.to_str().unwrap());
+    shell.feed_line(">$out both x; both y>>\"$out\"").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "x\ny\n");
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_redirect_errors() {
+    let shell = shell();
+
+    assert!(matches!(
+        shell.feed_line("both x >"),
+        Err(HackshellError::Syntax(_))
+    ));
+    assert!(matches!(
+        shell.feed_line("> file"),
+        Err(HackshellError::Syntax(_))
+    ));
+    assert!(
+        shell
+            .feed_line("both x > /nonexistent-hackshell-dir/file")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_printed_output_is_piped() {
+    let shell = shell();
+
+    assert_eq!(
+        shell.feed_line("both printed returned | cat").unwrap(),
+        Some("printed\nreturned".to_string())
+    );
+
+    shell.set_var("a", "1");
+    assert_eq!(
+        shell.feed_line("env | cat").unwrap(),
+        Some("a=1\n".to_string())
+    );
+}
+
+#[test]
+fn test_printed_output_is_substituted() {
+    let shell = shell();
+    shell.set_var("a", "1");
+
+    shell.feed_line("set b $(get a)").unwrap();
+    assert_eq!(shell.get_var("b"), Some("1".to_string()));
+}
@@ -0,0 +1,106 @@
+use hackshell::{CollisionPolicy, Hackshell, error::HackshellError};
+
+mod common;
+
+use common::{Buffer, labeled};
+
+fn run(shell: &Hackshell, line: &str) -> Option<String> {
+    shell.feed_line(line).unwrap()
+}
+
+#[test]
+fn test_remove_command_and_its_aliases() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell
+        .add_command(labeled(&["target", "tgt", "t"], "target"))
+        .add_command(labeled(&["other"], "other"));
+
+    let removed = shell.remove_command("tgt").unwrap();
+    assert_eq!(removed.commands(), &["target", "tgt", "t"]);
+
+    for name in ["target", "tgt", "t"] {
+        assert!(matches!(
+            shell.feed_line(name),
+            Err(HackshellError::CommandNotFound)
+        ));
+    }
+
+    assert_eq!(run(&shell, "other"), Some("other".to_string()));
+    assert!(shell.remove_command("tgt").is_none());
+
+    // Builtins too
+    assert!(shell.remove_command("env").is_some());
+    assert!(shell.feed_line("env").is_err());
+}
+
+#[test]
+fn test_overwrite_by_default() {
+    let shell = Hackshell::new("> ").unwrap();
+
+    shell
+        .add_command(labeled(&["a", "b"], "old"))
+        .add_command(labeled(&["b"], "new"));
+
+    assert_eq!(run(&shell, "a"), Some("old".to_string()));
+    assert_eq!(run(&shell, "b"), Some("new".to_string()));
+}
+
+#[test]
+fn test_error_policy() {
+    let shell = Hackshell::new("> ").unwrap();
+    let stderr = Buffer::default();
+
+    shell.set_stderr(stderr.clone());
+    shell.set_collision_policy(CollisionPolicy::Error);
+    shell.add_command(labeled(&["a", "b"], "old"));
+
+    // Nothing is added on collision, not even the free names
+    assert!(matches!(
+        shell.try_add_command(labeled(&["c", "b"], "new")),
+        Err(HackshellError::CommandExists(name)) if name == "b"
+    ));
+    assert!(shell.feed_line("c").is_err());
+
+    shell.add_command(labeled(&["a"], "new"));
+    assert_eq!(run(&shell, "a"), Some("old".to_string()));
+    assert_eq!(stderr.take(), "Command already exists: a\n");
+
+    assert!(shell.try_add_command(labeled(&["c"], "c")).is_ok());
+}
+
+#[test]
+fn test_warn_policy() {
+    let shell = Hackshell::new("> ").unwrap();
+    let stderr = Buffer::default();
+
+    shell.set_stderr(stderr.clone());
+    shell.set_collision_policy(CollisionPolicy::Warn);
+    shell
+        .add_command(labeled(&["a"], "old"))
+        .add_command(labeled(&["a"], "new"));
+
+    assert_eq!(run(&shell, "a"), Some("new".to_string()));
+    assert_eq!(stderr.take(), "Warning: command a overwritten\n");
+}
+
+#[test]
+fn test_replace_command() {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.set_collision_policy(CollisionPolicy::Error);
+
+    shell
+        .add_command(labeled(&["target", "tgt"], "old"))
+        .replace_command(labeled(&["target"], "new"));
+
+    assert_eq!(run(&shell, "target"), Some("new".to_string()));
+
+    // The other names of the replaced command are gone
+    assert!(shell.feed_line("tgt").is_err());
+
+    let help = shell
+        .get_commands()
+        .iter()
+        .filter(|c| c.help() == "Returns its label")
+        .count();
+    assert_eq!(help, 1);
+}
@@ -0,0 +1,193 @@
+use std::{fs, io::Cursor};
+
+use hackshell::{OnError, error::HackshellError};
+
+mod common;
+
+use common::{Buffer, log_shell};
+
+const SCRIPT: &str = "# Setup
+set target 10.0.0.1
+
+   # indented comment
+log $target; log b
+log fail here
+log last
+";
+
+#[test]
+fn test_run_reader() {
+    let (shell, calls) = log_shell(" ");
+
+    assert_eq!(
+        shell
+            .run_reader(Cursor::new("log a\n\nlog b # comment\n"))
+            .unwrap(),
+        Some("b".to_string())
+    );
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b"]);
+}
+
+#[test]
+fn test_output_of_last_command_line() {
+    let (shell, _calls) = log_shell(" ");
+
+    for script in [
+        "log a\nlog b\n# done",
+        "log a\nlog b\n\n\n",
+        "log b\n  # done\n",
+    ] {
+        assert_eq!(
+            shell.run_reader(Cursor::new(script)).unwrap(),
+            Some("b".to_string()),
+            "{:?}",
+            script
+        );
+    }
+}
+
+#[test]
+fn test_stop_on_error() {
+    let (shell, calls) = log_shell(" ");
+
+    let err = shell.run_reader(Cursor::new(SCRIPT)).unwrap_err();
+
+    assert!(matches!(
+        err,
+        HackshellError::Script {
+            file: None,
+            line: 6,
+            ..
+        }
+    ));
+    assert_eq!(err.to_string(), "line 6: failed: fail here");
+    assert_eq!(*calls.lock().unwrap(), vec!["10.0.0.1", "b", "fail here"]);
+}
+
+#[test]
+fn test_continue_on_error() {
+    let (shell, calls) = log_shell(" ");
+    let stderr = Buffer::default();
+
+    shell.set_stderr(stderr.clone());
+    shell.set_script_on_error(OnError::Continue);
+
+    let err = shell
+        .run_reader(Cursor::new("log fail 1\nlog a\nlog 'unterminated\nlog b"))
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "line 1: failed: fail 1");
+    assert_eq!(*calls.lock().unwrap(), vec!["fail 1", "a", "b"]);
+
+    let stderr = stderr.take();
+    let lines: Vec<&str> = stderr.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "line 1: failed: fail 1");
+    assert!(lines[1].starts_with("line 3: Syntax error"));
+}
+
+#[test]
+fn test_exit_stops_the_script() {
+    let (shell, calls) = log_shell(" ");
+    shell.set_script_on_error(OnError::Continue);
+
+    assert!(matches!(
+        shell.run_reader(Cursor::new("log a\nexit\nlog b")),
+        Err(HackshellError::Exit)
+    ));
+    assert_eq!(*calls.lock().unwrap(), vec!["a"]);
+}
+
+#[test]
+fn test_run_script() {
+    let (shell, _) = log_shell(" ");
+    let path = std::env::temp_dir().join(format!("hackshell-script-{}", std::process::id()));
+
+    fs::write(&path, SCRIPT).unwrap();
+    let err = shell.run_script(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(shell.get_var("target"), Some("10.0.0.1".to_string()));
+    assert_eq!(
+        err.to_string(),
+        format!("{}:6: failed: fail here", path.display())
+    );
+
+    assert!(shell.run_script(&path).is_err());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_run_reader_async() {
+    let (shell, calls) = log_shell(" ");
+
+    assert_eq!(
+        shell
+            .run_reader_async(Cursor::new("log a\nlog b"))
+            .await
+            .unwrap(),
+        Some("b".to_string())
+    );
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b"]);
+}
+
+fn temp_script(name: &str, content: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("hackshell-{}-{}", std::process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_source() {
+    let (shell, calls) = log_shell(" ");
+    let inner = temp_script("inner", "log inner\n\nlog fail inner\n");
+    let outer = temp_script(
+        "outer",
+        &format!("set a 1\nsource {}\nlog after", inner.display()),
+    );
+
+    let err = shell
+        .feed_line(&format!("source {}", outer.display()))
+        .unwrap_err();
+
+    fs::remove_file(&inner).unwrap();
+    fs::remove_file(&outer).unwrap();
+
+    assert_eq!(shell.get_var("a"), Some("1".to_string()));
+    assert_eq!(*calls.lock().unwrap(), vec!["inner", "fail inner"]);
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "{}:2: {}:3: failed: fail inner",
+            outer.display(),
+            inner.display()
+        )
+    );
+
+    assert!(shell.feed_line("source").is_err());
+    assert!(
+        shell
+            .feed_line(&format!("source {}", outer.display()))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_rc_file() {
+    let (shell, calls) = log_shell(" ");
+    let rc = temp_script("rc", "# rc\nset a 1\nlog from rc\nnope\n");
+
+    shell.set_rc_file(&rc);
+
+    // The rc file runs before the first line is read
+    let err = shell.run().unwrap_err();
+    fs::remove_file(&rc).unwrap();
+
+    assert_eq!(
+        err.to_string(),
+        format!("{}:4: Command not found", rc.display())
+    );
+    assert_eq!(shell.get_var("a"), Some("1".to_string()));
+    assert_eq!(*calls.lock().unwrap(), vec!["from rc"]);
+}
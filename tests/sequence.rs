@@ -0,0 +1,147 @@
+use hackshell::error::HackshellError;
+
+mod common;
+
+use common::log_shell;
+
+#[test]
+fn test_semicolon_runs_everything() {
+    let (shell, calls) = log_shell(" ");
+
+    assert_eq!(
+        shell.feed_line("log a; log b;log c;").unwrap(),
+        Some("c".to_string())
+    );
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_semicolon_returns_first_error() {
+    let (shell, calls) = log_shell(" ");
+
+    let err = shell
+        .feed_line("log fail 1; log b; log fail 2")
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "failed: fail 1");
+    assert_eq!(*calls.lock().unwrap(), vec!["fail 1", "b", "fail 2"]);
+}
+
+#[test]
+fn test_and_short_circuits() {
+    let (shell, calls) = log_shell(" ");
+
+    assert_eq!(
+        shell.feed_line("log a && log b").unwrap(),
+        Some("b".to_string())
+    );
+    assert!(shell.feed_line("log fail && log skipped").is_err());
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b", "fail"]);
+}
+
+#[test]
+fn test_or_handles_errors() {
+    let (shell, calls) = log_shell(" ");
+
+    assert_eq!(
+        shell.feed_line("log fail || log recovered").unwrap(),
+        Some("recovered".to_string())
+    );
+    assert_eq!(
+        shell.feed_line("log a || log skipped").unwrap(),
+        Some("a".to_string())
+    );
+    assert_eq!(*calls.lock().unwrap(), vec!["fail", "recovered", "a"]);
+}
+
+#[test]
+fn test_mixed_operators() {
+    let (shell, calls) = log_shell(" ");
+
+    assert_eq!(
+        shell
+            .feed_line("log fail && log x || log y; log z && log fail 2 || log w")
+            .unwrap(),
+        Some("w".to_string())
+    );
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec!["fail", "y", "z", "fail 2", "w"]
+    );
+}
+
+#[test]
+fn test_set_then_use() {
+    let (shell, _) = log_shell(" ");
+
+    shell.feed_line("set target 10.0.0.1; set port 22").unwrap();
+
+    assert_eq!(shell.get_var("target"), Some("10.0.0.1".to_string()));
+    assert_eq!(shell.get_var("port"), Some("22".to_string()));
+}
+
+#[test]
+fn test_exit_stops_the_line() {
+    let (shell, calls) = log_shell(" ");
+
+    assert!(matches!(
+        shell.feed_line("log a; exit || log b; log c"),
+        Err(HackshellError::Exit)
+    ));
+    assert_eq!(*calls.lock().unwrap(), vec!["a"]);
+}
+
+#[test]
+fn test_newlines_separate_commands() {
+    let (shell, calls) = log_shell(" ");
+
+    shell.feed_line("log a\n\nlog b &&\n log c").unwrap();
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_sequence_syntax_errors() {
+    let (shell, calls) = log_shell(" ");
+
+    for line in [
+        "; log a",
+        "log a;; log b",
+        "log a &&",
+        "|| log a",
+        "log a & log b",
+    ] {
+        assert!(
+            matches!(shell.feed_line(line), Err(HackshellError::Syntax(_))),
+            "{}",
+            line
+        );
+    }
+
+    // Nothing ran
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_quoted_operators_are_literal() {
+    let (shell, _) = log_shell(" ");
+
+    assert_eq!(
+        shell.feed_line(r#"log 'a; b' "&&" c\;"#).unwrap(),
+        Some("a; b && c;".to_string())
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_sequence_async() {
+    let (shell, calls) = log_shell(" ");
+
+    // The future can be moved to another task
+    let result =
+        tokio::spawn(async move { shell.feed_line_async("log fail || log b; log c").await })
+            .await
+            .unwrap();
+
+    assert_eq!(result.unwrap(), Some("c".to_string()));
+    assert_eq!(*calls.lock().unwrap(), vec!["fail", "b", "c"]);
+}
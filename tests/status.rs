@@ -0,0 +1,119 @@
+use std::io::Cursor;
+
+use hackshell::{Command, CommandResult, Hackshell, error::HackshellError};
+
+/// Fails with the status given as argument, succeeds without one
+struct Fail;
+
+impl Command for Fail {
+    fn commands(&self) -> &'static [&'static str] {
+        &["fail"]
+    }
+
+    fn help(&self) -> &'static str {
+        "Fails with a status"
+    }
+
+    fn run(&self, _s: &Hackshell, cmd: &[&str]) -> CommandResult {
+        match cmd.get(1) {
+            Some(code) => Err(HackshellError::Status {
+                code: code.parse()?,
+                message: String::new(),
+            }
+            .into()),
+            None => Ok(Some("ok".into())),
+        }
+    }
+}
+
+fn shell() -> Hackshell {
+    let shell = Hackshell::new("> ").unwrap();
+    shell.add_command(Fail);
+    shell
+}
+
+#[test]
+fn test_last_status() {
+    let shell = shell();
+
+    assert_eq!(shell.last_status(), 0);
+
+    let err = shell.feed_line("fail 3").unwrap_err();
+    assert_eq!(err.status(), 3);
+    assert_eq!(err.to_string(), "Exited with status 3");
+    assert_eq!(shell.last_status(), 3);
+
+    shell.feed_line("fail").unwrap();
+    assert_eq!(shell.last_status(), 0);
+
+    // Generic errors
+    assert!(shell.feed_line("fail x").is_err());
+    assert_eq!(shell.last_status(), 1);
+
+    assert!(shell.feed_line("nope").is_err());
+    assert_eq!(shell.last_status(), 127);
+
+    assert!(shell.feed_line("sleep x").is_err());
+    assert_eq!(shell.last_status(), 2);
+
+    assert!(shell.feed_line("fail 'unterminated").is_err());
+    assert_eq!(shell.last_status(), 2);
+}
+
+#[test]
+fn test_status_variable() {
+    let shell = shell();
+
+    // The status of the last command, even in a failed list
+    assert!(shell.feed_line("fail 4; set a $?; fail; set b $?").is_err());
+    assert_eq!(shell.get_var("a"), Some("4".to_string()));
+    assert_eq!(shell.get_var("b"), Some("0".to_string()));
+    assert_eq!(shell.last_status(), 0);
+
+    assert_eq!(
+        shell
+            .feed_line("fail 5 || if test $? -eq 5; then fail; fi")
+            .unwrap(),
+        Some("ok".to_string())
+    );
+
+    shell.feed_line("fail 6 || set c ${?}").unwrap();
+    assert_eq!(shell.get_var("c"), Some("6".to_string()));
+}
+
+#[test]
+fn test_compound_status() {
+    let shell = shell();
+
+    assert!(shell.feed_line("! fail").is_err());
+    assert_eq!(shell.last_status(), 1);
+
+    shell.feed_line("! fail 2").unwrap();
+    assert_eq!(shell.last_status(), 0);
+
+    // A pipeline has the status of its last command
+    assert!(shell.feed_line("fail | fail 7").is_err());
+    assert_eq!(shell.last_status(), 7);
+
+    shell.feed_line("def f { fail $1 }").unwrap();
+    assert!(shell.feed_line("f 8").is_err());
+    assert_eq!(shell.last_status(), 8);
+
+    shell.feed_line("if fail 9; then fail; fi").unwrap();
+    assert_eq!(shell.last_status(), 0);
+
+    assert!(shell.feed_line("test a = b").is_err());
+    assert_eq!(shell.last_status(), 1);
+}
+
+#[test]
+fn test_script_status() {
+    let shell = shell();
+
+    let err = shell
+        .run_reader(Cursor::new("fail\nfail 42\n"))
+        .unwrap_err();
+
+    assert_eq!(err.status(), 42);
+    assert_eq!(shell.last_status(), 42);
+}
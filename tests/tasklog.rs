@@ -0,0 +1,180 @@
+use std::{
+    io::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use hackshell::{
+    Hackshell, Output, TaskOptions,
+    taskpool::{TaskLog, TaskPool},
+};
+
+mod common;
+
+use common::Buffer;
+
+fn wait_closed(log: &TaskLog) {
+    while !log.is_closed() {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_task_output_is_captured() {
+    let shell = Hackshell::new("> ").unwrap();
+    let buf = Buffer::default();
+    shell.set_stdout(buf.clone());
+
+    let s = shell.clone();
+    shell.spawn("scan", TaskOptions::default(), move |_run| {
+        writeln!(s.stdout(), "port 22 open").unwrap();
+        write!(s.stderr(), "port 80 ").unwrap();
+        write!(s.stderr(), "filtered").unwrap();
+        None
+    });
+
+    let log = shell.task_log("scan").unwrap();
+    wait_closed(&log);
+
+    assert_eq!(log.lines(), vec!["port 22 open", "port 80 filtered"]);
+    assert!(buf.take().is_empty());
+
+    // The log is kept after the task ends
+    assert_eq!(shell.feed_line("task --logs scan").unwrap(), None,);
+    assert_eq!(buf.take(), "port 22 open\nport 80 filtered\n");
+    assert!(shell.feed_line("task --logs nope").is_err());
+}
+
+#[test]
+fn test_task_log_wins_over_spawner_redirect() {
+    let shell = Hackshell::new("> ").unwrap();
+    let spawner = Buffer::default();
+    let inner = Buffer::default();
+
+    // Handle of a command run as `cmd > file`, kept by the task it spawns
+    let s = shell.with_stdout(Output::new(spawner.clone()));
+    let inner_out = inner.clone();
+    shell.spawn("scan", TaskOptions::default(), move |_run| {
+        writeln!(s.stdout(), "to the log").unwrap();
+        writeln!(s.with_stdout(Output::new(inner_out)).stdout(), "redirected").unwrap();
+        None
+    });
+
+    let log = shell.task_log("scan").unwrap();
+    wait_closed(&log);
+
+    assert_eq!(log.lines(), vec!["to the log"]);
+    assert!(spawner.take().is_empty());
+    assert_eq!(inner.take(), "redirected\n");
+}
+
+#[test]
+fn test_log_capacity() {
+    let pool = TaskPool::default();
+    let opts = TaskOptions {
+        log_capacity: Some(3),
+        ..Default::default()
+    };
+
+    pool.spawn("count", opts, |_run| {
+        let mut log = TaskLog::current().unwrap();
+        for i in 0..10 {
+            writeln!(log, "{}", i).unwrap();
+        }
+        None
+    });
+
+    let log = pool.log("count").unwrap();
+    wait_closed(&log);
+    assert_eq!(log.lines(), vec!["7", "8", "9"]);
+    assert!(TaskLog::current().is_none());
+}
+
+#[test]
+fn test_wait_lines() {
+    let pool = TaskPool::default();
+    let go = Arc::new(AtomicBool::new(false));
+    let go_task = go.clone();
+
+    pool.spawn("slow", TaskOptions::default(), move |_run| {
+        let mut log = TaskLog::current().unwrap();
+        writeln!(log, "first").unwrap();
+        while !go_task.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        writeln!(log, "second").unwrap();
+        None
+    });
+
+    let log = pool.log("slow").unwrap();
+    let (lines, seen) = log.wait_lines(0, Duration::from_secs(5));
+    assert_eq!((lines, seen), (vec!["first".to_string()], 1));
+
+    go.store(true, Ordering::Relaxed);
+    let (lines, seen) = log.wait_lines(seen, Duration::from_secs(5));
+    assert_eq!((lines, seen), (vec!["second".to_string()], 2));
+}
+
+#[test]
+fn test_logs_leave_with_the_history() {
+    let pool = TaskPool::default();
+    pool.set_history_capacity(1);
+
+    for name in ["first", "second"] {
+        pool.spawn(name, TaskOptions::default(), |_run| None);
+
+        while !pool.history().iter().any(|r| r.meta.name == name) {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    assert!(pool.log("first").is_none());
+    assert!(pool.log("second").is_some());
+
+    pool.set_history_capacity(0);
+    assert!(pool.log("second").is_none());
+}
+
+#[test]
+fn test_attach_until_task_ends() {
+    let shell = Hackshell::new("> ").unwrap();
+    let buf = Buffer::default();
+
+    let s = shell.clone();
+    shell.spawn("beacon", TaskOptions::default(), move |_run| {
+        for i in 0..3 {
+            writeln!(s.stdout(), "beacon {}", i).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+        None
+    });
+
+    shell
+        .with_stdout(Output::new(buf.clone()))
+        .feed_line("task --follow beacon")
+        .unwrap();
+    assert_eq!(buf.take(), "beacon 0\nbeacon 1\nbeacon 2\n");
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_async_task_output_is_captured() {
+    let shell = Hackshell::new("> ").unwrap();
+
+    let s = shell.clone();
+    shell.spawn_async("listener", TaskOptions::default(), async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        writeln!(s.stdout(), "connection from 10.0.0.2").unwrap();
+        None
+    });
+
+    shell.join_async("listener").await.unwrap();
+    assert_eq!(
+        shell.task_log("listener").unwrap().lines(),
+        vec!["connection from 10.0.0.2"]
+    );
+}
@@ -1,9 +1,20 @@
+// Lints raised by the original tests, kept as they were written
+#![allow(
+    clippy::never_loop,
+    clippy::single_component_path_imports,
+    clippy::len_zero
+)]
+
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use std::time::Duration;
 
-use hackshell::taskpool::{TaskOptions, TaskPool};
+use hackshell::taskpool::{TaskOptions, TaskOutcome, TaskPool, TaskRecord};
+
+mod common;
+
+use common::Buffer;
 
 #[test]
 fn test_spawn_and_execute_task() {
@@ -210,11 +221,317 @@ fn test_clone_pool() {
     assert_eq!(pool2.get_all().len(), 0);
 }
 
+/// Wait for the history of the pool to have `n` records
+fn wait_history(pool: &TaskPool, n: usize) -> Vec<TaskRecord> {
+    for _ in 0..200 {
+        let history = pool.history();
+        if history.len() >= n {
+            return history;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("history has less than {} records", n);
+}
+
+#[test]
+fn test_history_returned_task() {
+    let pool = TaskPool::default();
+
+    pool.spawn("answer", TaskOptions::default(), |_run| {
+        thread::sleep(Duration::from_millis(20));
+        Some(Box::new(42u32))
+    });
+
+    let record = wait_history(&pool, 1).remove(0);
+    assert_eq!(record.meta.name, "answer");
+    assert_eq!(record.outcome, TaskOutcome::Returned);
+    assert!(record.ended >= record.meta.started);
+    assert!(record.duration() >= chrono::TimeDelta::milliseconds(20));
+    assert!(pool.get_all().is_empty());
+
+    let output = record.take_output().unwrap();
+    assert_eq!(output.downcast_ref::<u32>(), Some(&42));
+    assert!(pool.history()[0].take_output().is_none());
+}
+
+#[test]
+fn test_history_panicked_and_killed_tasks() {
+    let pool = TaskPool::default();
+
+    pool.spawn("crash", TaskOptions::default(), |_run| panic!("boom"));
+    assert_eq!(wait_history(&pool, 1)[0].outcome, TaskOutcome::Panicked);
+
+    pool.spawn("loop", TaskOptions::default(), |run| {
+        while run.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        Some(Box::new(()))
+    });
+    pool.remove("loop").unwrap();
+
+    let record = wait_history(&pool, 2).remove(1);
+    assert_eq!(record.meta.name, "loop");
+    assert_eq!(record.outcome, TaskOutcome::Killed);
+    assert!(record.take_output().is_some());
+}
+
+#[test]
+fn test_history_capacity_and_join() {
+    let pool = TaskPool::default();
+    pool.set_history_capacity(2);
+
+    for name in ["a", "b", "c"] {
+        pool.spawn(name, TaskOptions::default(), |_run| Some(Box::new(1u8)));
+        assert!(pool.join(name).unwrap().is_some());
+    }
+
+    let history = wait_history(&pool, 2);
+    let names: Vec<_> = history.iter().map(|r| r.meta.name.as_str()).collect();
+    assert_eq!(names, vec!["b", "c"]);
+
+    // Joining takes the output
+    assert!(history[1].take_output().is_none());
+
+    pool.set_history_capacity(0);
+    assert!(pool.history().is_empty());
+}
+
+#[test]
+fn test_task_history_command() {
+    use hackshell::Hackshell;
+
+    let shell = Hackshell::new("> ").unwrap();
+
+    assert!(shell.feed_line("task --history").is_ok());
+
+    shell.spawn("quick", TaskOptions::default(), |_run| None);
+    shell.join("quick").unwrap();
+
+    shell.feed_line("set table \"$(task --history)\"").unwrap();
+    let table = shell.get_var("table").unwrap();
+    assert!(table.contains("quick") && table.contains("returned"));
+}
+
+#[test]
+fn test_end_listener() {
+    let pool = TaskPool::default();
+    let ended = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let ended_listener = ended.clone();
+
+    pool.on_end(move |record| {
+        ended_listener
+            .lock()
+            .unwrap()
+            .push((record.meta.name.clone(), record.outcome));
+    });
+
+    pool.spawn("ok", TaskOptions::default(), |_run| None);
+    pool.join("ok").unwrap();
+    pool.spawn("crash", TaskOptions::default(), |_run| panic!("boom"));
+    assert!(pool.join("crash").is_err());
+
+    assert_eq!(
+        *ended.lock().unwrap(),
+        vec![
+            ("ok".to_string(), TaskOutcome::Returned),
+            ("crash".to_string(), TaskOutcome::Panicked)
+        ]
+    );
+}
+
+#[test]
+fn test_panicking_listener_on_panicked_task() {
+    let pool = TaskPool::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_listener = calls.clone();
+
+    // Panicking while the task unwinds would abort the process
+    pool.on_end(move |record| {
+        calls_listener.fetch_add(1, Ordering::Relaxed);
+        if record.outcome == TaskOutcome::Panicked {
+            panic!("listener");
+        }
+    });
+
+    pool.spawn("crash", TaskOptions::default(), |_run| panic!("boom"));
+    assert!(pool.join("crash").is_err());
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert_eq!(pool.history()[0].outcome, TaskOutcome::Panicked);
+}
+
+#[test]
+fn test_task_end_notification() {
+    use hackshell::Hackshell;
+
+    let shell = Hackshell::new("> ").unwrap();
+    let buf = Buffer::default();
+    shell.set_stdout(buf.clone());
+
+    shell.spawn("silent", TaskOptions::default(), |_run| None);
+    shell.join("silent").unwrap();
+
+    let opts = TaskOptions {
+        notify: true,
+        ..Default::default()
+    };
+    shell.spawn("scan", opts.clone(), |_run| None);
+    shell.join("scan").unwrap();
+    shell.spawn("loop", opts, |run| {
+        while run.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    });
+    shell.terminate("loop").unwrap();
+
+    let mut printed = String::new();
+    for _ in 0..200 {
+        printed += &buf.take();
+        if printed.lines().count() >= 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let lines: Vec<_> = printed.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("[scan] Done after "));
+    assert!(lines[1].starts_with("[loop] Killed after "));
+}
+
+#[test]
+fn test_task_timeout() {
+    let pool = TaskPool::default();
+    let opts = TaskOptions {
+        timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+    };
+
+    pool.spawn("slow", opts.clone(), |run| {
+        while run.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    });
+    pool.spawn("fast", opts, |_run| None);
+
+    let history = wait_history(&pool, 2);
+    let outcome = |name: &str| {
+        history
+            .iter()
+            .find(|r| r.meta.name == name)
+            .map(|r| r.outcome)
+    };
+
+    assert_eq!(outcome("fast"), Some(TaskOutcome::Returned));
+    assert_eq!(outcome("slow"), Some(TaskOutcome::TimedOut));
+    assert!(pool.get_all().is_empty());
+}
+
+#[test]
+fn test_task_deadline() {
+    let pool = TaskPool::default();
+    let deadline = chrono::Utc::now() + chrono::TimeDelta::milliseconds(50);
+
+    pool.spawn(
+        "scan",
+        TaskOptions {
+            timeout: Some(Duration::from_secs(60)),
+            deadline: Some(deadline),
+            ..Default::default()
+        },
+        |run| {
+            while run.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            None
+        },
+    );
+
+    // The earliest of the two is kept
+    assert_eq!(pool.get_all()[0].deadline, Some(deadline));
+
+    let record = wait_history(&pool, 1).remove(0);
+    assert_eq!(record.outcome, TaskOutcome::TimedOut);
+    assert!(record.ended >= deadline);
+}
+
 #[cfg(feature = "async")]
 mod async_tests {
     use super::*;
     use tokio;
 
+    #[tokio::test]
+    async fn test_async_task_timeout() {
+        let pool = TaskPool::default();
+
+        pool.spawn_async(
+            "listener",
+            TaskOptions {
+                timeout: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+            async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                None
+            },
+        );
+
+        for _ in 0..100 {
+            if !pool.history().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(pool.history()[0].outcome, TaskOutcome::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_async_tasks_ending_before_timeout() {
+        let pool = TaskPool::default();
+        let opts = TaskOptions {
+            timeout: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        for i in 0..50 {
+            pool.spawn_async(&format!("quick-{}", i), opts.clone(), async { None });
+        }
+
+        for _ in 0..100 {
+            if pool.history().len() == 50 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let history = pool.history();
+        assert_eq!(history.len(), 50);
+        assert!(history.iter().all(|r| r.outcome == TaskOutcome::Returned));
+        assert!(pool.get_all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_history_aborted_async_task() {
+        let pool = TaskPool::default();
+
+        pool.spawn_async("listener", TaskOptions::default(), async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            None
+        });
+        pool.remove("listener").unwrap();
+
+        for _ in 0..100 {
+            if !pool.history().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        assert_eq!(pool.history()[0].outcome, TaskOutcome::Killed);
+    }
+
     #[tokio::test]
     async fn test_spawn_async_task() {
         let pool = TaskPool::default();